@@ -0,0 +1,368 @@
+use bevy::{
+    audio::{AddAudioSource, Decodable, Sample, Source},
+    prelude::*,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::{
+    crackle::FireSound,
+    navigation::{FLOOR_MAX, FLOOR_MIN},
+    stream::StreamedAudio,
+    surface::{WALL_LEFT, WALL_RIGHT},
+    synth::Song,
+};
+
+// The rooms sound can be in.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Room {
+    Inside,
+    Outside,
+}
+
+// Add to entities marking an area as a room.
+#[derive(Component)]
+pub struct RoomRegion {
+    pub room: Room,
+    pub width: f32,
+}
+
+// How a room echoes.
+#[derive(Clone, Copy)]
+pub struct Reverb {
+    // How loud the echoes are against the sound itself.
+    pub mix: f32,
+    // How long they ring on for, from 0 gone at once to 1 forever.
+    pub decay: f32,
+}
+
+// Settings shared with a sound while it plays, so it changes with the rooms straight away.
+#[derive(Clone, Default)]
+pub struct AcousticControls(Arc<AcousticSettings>);
+
+// Each as the bits of an f32.
+#[derive(Default)]
+struct AcousticSettings {
+    mix: AtomicU32,
+    decay: AtomicU32,
+    // How muffled the sound is by walls, from 0 heard clearly to 1 from another room.
+    occlusion: AtomicU32,
+}
+
+// Audio assets that play with the acoustics of the room they're in. The acoustics belong to the asset, so
+// every player needs an asset of its own, made with `instance`, for each to sound like where it is.
+pub trait Acoustic: Asset + Decodable {
+    fn acoustics(&self) -> &AcousticControls;
+
+    // A copy of the sound to give one player, sharing the audio but with acoustics of its own.
+    fn instance(&self) -> Self;
+}
+
+// An `AudioSource` played with the acoustics of the room it's in.
+#[derive(Asset, TypePath)]
+pub struct RoomSound {
+    source: AudioSource,
+    acoustics: AcousticControls,
+}
+
+// Wraps a source to echo it around its room and muffle it through walls.
+pub struct Acoustics<S> {
+    source: S,
+    controls: AcousticControls,
+    // The settings heard, following the ones set so changes don't click.
+    mix: f32,
+    decay: f32,
+    occlusion: f32,
+    started: bool,
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+    // The muffled sound for each channel.
+    muffled: Vec<f32>,
+    channel: usize,
+}
+
+// A delay feeding back into itself, with the echoes dulling as they go.
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    filtered: f32,
+}
+
+// A delay that smears the echoes together without colouring them.
+struct Allpass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+// Milliseconds, picked not to line up with each other so the echoes don't ring.
+const COMB_DELAYS: [f32; 4] = [29.7, 37.1, 41.1, 43.7];
+const ALLPASS_DELAYS: [f32; 2] = [5.0, 1.7];
+const ALLPASS_FEEDBACK: f32 = 0.5;
+// How much each echo loses its high end.
+const DAMPING: f32 = 0.3;
+// The longest echoes can ring, keeping the feedback from running away.
+const MAX_DECAY: f32 = 0.9;
+
+// How muffled and quiet sound is from another room.
+const OCCLUDED_CUTOFF: f32 = 0.08;
+const OCCLUDED_VOLUME: f32 = 0.5;
+// How quickly the settings heard follow the ones set, per sample.
+const SMOOTHING: f32 = 0.0005;
+
+// Add the acoustics systems.
+pub fn add_systems(app: &mut App) {
+    app.add_audio_source::<RoomSound>()
+        .add_systems(Startup, init)
+        // Before transforms are propagated, which audio playback waits on, so new sounds are placed before
+        // they start playing.
+        .add_systems(
+            PostUpdate,
+            (
+                handle_acoustics::<RoomSound>,
+                handle_acoustics::<StreamedAudio>,
+                handle_acoustics::<Song>,
+                handle_acoustics::<FireSound>,
+            )
+                .before(TransformSystems::Propagate),
+        );
+}
+
+impl Room {
+    pub fn reverb(self) -> Reverb {
+        match self {
+            Room::Inside => Reverb { mix: 0.25, decay: 0.6 },
+            // The snow soaks up most of the sound outside.
+            Room::Outside => Reverb { mix: 0.05, decay: 0.3 },
+        }
+    }
+}
+
+// Find the room at a position, anywhere not marked is outside. Rooms run from floor to ceiling, so only
+// how far across matters.
+pub fn room_at<'a>(position: Vec2, mut regions: impl Iterator<Item = (&'a Transform, &'a RoomRegion)>) -> Room {
+    regions
+        .find(|(transform, region)| (transform.translation.x - position.x).abs() <= region.width / 2.0)
+        .map_or(Room::Outside, |(_, region)| region.room)
+}
+
+impl AcousticControls {
+    fn set(&self, reverb: Reverb, occlusion: f32) {
+        self.0.mix.store(reverb.mix.to_bits(), Ordering::Relaxed);
+        self.0.decay.store(reverb.decay.to_bits(), Ordering::Relaxed);
+        self.0.occlusion.store(occlusion.to_bits(), Ordering::Relaxed);
+    }
+
+    fn get(&self) -> (f32, f32, f32) {
+        let load = |value: &AtomicU32| f32::from_bits(value.load(Ordering::Relaxed));
+        (load(&self.0.mix), load(&self.0.decay), load(&self.0.occlusion))
+    }
+}
+
+impl RoomSound {
+    pub fn new(source: AudioSource) -> Self {
+        RoomSound {
+            source,
+            acoustics: AcousticControls::default(),
+        }
+    }
+}
+
+impl Decodable for RoomSound {
+    type DecoderItem = f32;
+    type Decoder = Acoustics<<AudioSource as Decodable>::Decoder>;
+
+    fn decoder(&self) -> Self::Decoder {
+        Acoustics::new(self.source.decoder(), self.acoustics.clone())
+    }
+}
+
+impl Acoustic for RoomSound {
+    fn acoustics(&self) -> &AcousticControls {
+        &self.acoustics
+    }
+
+    fn instance(&self) -> Self {
+        RoomSound::new(self.source.clone())
+    }
+}
+
+impl Comb {
+    fn process(&mut self, input: f32, feedback: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.filtered = output * (1.0 - DAMPING) + self.filtered * DAMPING;
+        self.buffer[self.index] = input + self.filtered * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+impl Allpass {
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.index];
+        self.buffer[self.index] = input + delayed * ALLPASS_FEEDBACK;
+        self.index = (self.index + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+impl<S> Acoustics<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    pub fn new(source: S, controls: AcousticControls) -> Self {
+        // Channels are interleaved, so the delays stretch to cover them all.
+        let samples_per_second = source.sample_rate() as f32 * f32::from(source.channels().max(1));
+        let delay = |milliseconds: f32| vec![0.0; ((milliseconds / 1000.0 * samples_per_second) as usize).max(1)];
+
+        Acoustics {
+            combs: COMB_DELAYS
+                .iter()
+                .map(|&milliseconds| Comb {
+                    buffer: delay(milliseconds),
+                    index: 0,
+                    filtered: 0.0,
+                })
+                .collect(),
+            allpasses: ALLPASS_DELAYS
+                .iter()
+                .map(|&milliseconds| Allpass {
+                    buffer: delay(milliseconds),
+                    index: 0,
+                })
+                .collect(),
+            muffled: vec![0.0; usize::from(source.channels().max(1))],
+            source,
+            controls,
+            mix: 0.0,
+            decay: 0.0,
+            occlusion: 0.0,
+            started: false,
+            channel: 0,
+        }
+    }
+}
+
+impl<S> Iterator for Acoustics<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.source.next()?.to_f32();
+
+        // Start with the settings as they are, then follow them smoothly.
+        let (mix, decay, occlusion) = self.controls.get();
+        if !self.started {
+            (self.mix, self.decay, self.occlusion) = (mix, decay, occlusion);
+            self.started = true;
+        }
+        self.mix += (mix - self.mix) * SMOOTHING;
+        self.decay += (decay - self.decay) * SMOOTHING;
+        self.occlusion += (occlusion - self.occlusion) * SMOOTHING;
+
+        let feedback = self.decay.clamp(0.0, 1.0) * MAX_DECAY;
+        let mut echoes = self
+            .combs
+            .iter_mut()
+            .map(|comb| comb.process(sample, feedback))
+            .sum::<f32>()
+            / COMB_DELAYS.len() as f32;
+        for allpass in &mut self.allpasses {
+            echoes = allpass.process(echoes);
+        }
+        let wet = sample + echoes * self.mix;
+
+        // Walls take the high end off, and some of the rest.
+        if self.channel >= self.muffled.len() {
+            self.channel = 0;
+        }
+        let cutoff = 1.0 - self.occlusion * (1.0 - OCCLUDED_CUTOFF);
+        let muffled = &mut self.muffled[self.channel];
+        *muffled += (wet - *muffled) * cutoff;
+        self.channel += 1;
+
+        Some(*muffled * (1.0 - self.occlusion * (1.0 - OCCLUDED_VOLUME)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.source.size_hint()
+    }
+}
+
+impl<S> Source for Acoustics<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+
+    fn try_seek(&mut self, position: Duration) -> Result<(), bevy::audio::SeekError> {
+        self.channel = 0;
+        self.source.try_seek(position)
+    }
+}
+
+// Give every sound its room's echo, muffled when the listener is in a different room.
+fn handle_acoustics<T: Acoustic>(
+    sounds: Res<Assets<T>>,
+    transforms: TransformHelper,
+    listener: Single<Entity, With<SpatialListener>>,
+    regions: Query<(&Transform, &RoomRegion)>,
+    players: Query<(Entity, &AudioPlayer<T>, Has<Transform>)>,
+) {
+    // Global transforms aren't up to date yet, so work them out from the hierarchy.
+    let Ok(listener) = transforms.compute_global_transform(*listener) else {
+        return;
+    };
+    let listener_room = room_at(listener.translation().truncate(), regions.iter());
+
+    for (entity, player, placed) in &players {
+        let Some(sound) = sounds.get(&player.0) else {
+            continue;
+        };
+
+        // Sounds that aren't placed anywhere, like the man's footsteps, are heard in the listener's room.
+        let room = if placed {
+            let Ok(transform) = transforms.compute_global_transform(entity) else {
+                continue;
+            };
+            room_at(transform.translation().truncate(), regions.iter())
+        } else {
+            listener_room
+        };
+        let occlusion = if room == listener_room { 0.0 } else { 1.0 };
+        sound.acoustics().set(room.reverb(), occlusion);
+    }
+}
+
+// Room initialization.
+fn init(mut commands: Commands) {
+    let floor_y = (FLOOR_MAX.y + FLOOR_MIN.y) / 2.0;
+
+    // Mark the inside of the house, everywhere else is outside.
+    commands.spawn((
+        Transform::from_translation(Vec3::new((WALL_LEFT + WALL_RIGHT) / 2.0, floor_y, 0.0)),
+        RoomRegion {
+            room: Room::Inside,
+            width: WALL_RIGHT - WALL_LEFT,
+        },
+    ));
+}
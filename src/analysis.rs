@@ -0,0 +1,405 @@
+use bevy::{
+    audio::{AudioSinkPlayback, Sample, Source},
+    prelude::*,
+};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{stream::StreamedAudio, synth::Song};
+
+// Samples recently played by a tapped source, mixed down to mono and shared with the audio thread.
+#[derive(Clone, Default)]
+pub struct Tap(Arc<Mutex<TapBuffer>>);
+
+#[derive(Default)]
+struct TapBuffer {
+    samples: VecDeque<f32>,
+    sample_rate: u32,
+}
+
+// Wraps a source to copy what it plays into a tap, leaving the audio itself untouched.
+pub struct Tapped<S> {
+    source: S,
+    tap: Tap,
+    pending: Vec<f32>,
+    frame: f32,
+    channel: u16,
+}
+
+// Add to a streamed audio or song player to analyse it as it plays.
+#[derive(Component)]
+pub struct Analysed;
+
+// Message sent when the analysed music hits a beat.
+#[derive(Message)]
+pub struct Beat;
+
+// Levels of the analysed music in a few frequency bands from low to high, each between 0 and 1.
+#[derive(Default, PartialEq, Resource)]
+pub struct Spectrum(pub [f32; BAND_COUNT]);
+
+// Running analysis of the music playing.
+#[derive(Default, Resource)]
+struct MusicAnalysis {
+    // The latest samples, for working out the spectrum.
+    window: VecDeque<f32>,
+    // Samples waiting for a full block.
+    block: Vec<f32>,
+    // Energies of recent blocks, for judging whether a new block stands out.
+    history: VecDeque<f32>,
+    // Seconds since the last beat, so one hit isn't counted twice.
+    since_beat: f32,
+}
+
+// Samples copied to the tap at a time, to keep locking down on the audio thread.
+const TAP_CHUNK: usize = 512;
+// The most samples a tap holds if nothing is reading them.
+const TAP_CAPACITY: usize = 48_000;
+
+const BLOCK_SIZE: usize = 1024;
+// Roughly a second of blocks.
+const HISTORY_LENGTH: usize = 43;
+const BEAT_SENSITIVITY: f32 = 1.4;
+const MIN_BEAT_ENERGY: f32 = 0.0005;
+const MIN_BEAT_GAP: f32 = 0.25;
+
+pub const BAND_COUNT: usize = 4;
+// Samples per spectrum, a power of two for the FFT.
+const FFT_SIZE: usize = 1024;
+const LOWEST_FREQUENCY: f32 = 60.0;
+const HIGHEST_FREQUENCY: f32 = 8000.0;
+// Decibels below full scale that count as silence.
+const SPECTRUM_FLOOR: f32 = 60.0;
+// How quickly levels drop back, per second, so the bars don't jitter.
+const SPECTRUM_FALL: f32 = 2.5;
+
+// Add the analysis systems.
+pub fn add_systems(app: &mut App) {
+    app.init_resource::<MusicAnalysis>()
+        .init_resource::<Spectrum>()
+        .add_message::<Beat>()
+        .add_systems(Update, handle_analysis);
+}
+
+impl Tap {
+    fn push(&self, samples: &[f32], sample_rate: u32) {
+        if let Ok(mut buffer) = self.0.lock() {
+            buffer.sample_rate = sample_rate;
+            buffer.samples.extend(samples);
+            let excess = buffer.samples.len().saturating_sub(TAP_CAPACITY);
+            buffer.samples.drain(..excess);
+        }
+    }
+
+    // Take everything played since the last drain, along with its sample rate.
+    pub fn drain(&self) -> (Vec<f32>, u32) {
+        match self.0.lock() {
+            Ok(mut buffer) => (buffer.samples.drain(..).collect(), buffer.sample_rate),
+            Err(_) => (Vec::new(), 0),
+        }
+    }
+}
+
+impl<S> Tapped<S> {
+    pub fn new(source: S, tap: Tap) -> Self {
+        Tapped {
+            source,
+            tap,
+            pending: Vec::with_capacity(TAP_CHUNK),
+            frame: 0.0,
+            channel: 0,
+        }
+    }
+}
+
+impl<S> Iterator for Tapped<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let sample = self.source.next()?;
+
+        // Average each frame's channels into one sample.
+        self.frame += sample.to_f32();
+        self.channel += 1;
+        let channels = self.source.channels().max(1);
+        if self.channel >= channels {
+            self.pending.push(self.frame / f32::from(channels));
+            self.frame = 0.0;
+            self.channel = 0;
+
+            if self.pending.len() >= TAP_CHUNK {
+                self.tap.push(&self.pending, self.source.sample_rate());
+                self.pending.clear();
+            }
+        }
+
+        Some(sample)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.source.size_hint()
+    }
+}
+
+impl<S> Source for Tapped<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+
+    fn try_seek(&mut self, position: Duration) -> Result<(), bevy::audio::SeekError> {
+        self.pending.clear();
+        self.frame = 0.0;
+        self.channel = 0;
+        self.source.try_seek(position)
+    }
+}
+
+// The mean energy of a block of samples.
+fn energy(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32
+}
+
+// Whether a block's energy stands out enough from the blocks before it to count as a beat.
+fn is_onset(energy: f32, history: &VecDeque<f32>) -> bool {
+    if history.len() < HISTORY_LENGTH || energy < MIN_BEAT_ENERGY {
+        return false;
+    }
+
+    let average = history.iter().sum::<f32>() / history.len() as f32;
+    energy > average * BEAT_SENSITIVITY
+}
+
+// Transform samples into the frequency domain in place, with the imaginary parts alongside.
+fn fft(real: &mut [f32], imaginary: &mut [f32]) {
+    let size = real.len();
+
+    // Put the samples into bit reversed order.
+    let mut j = 0;
+    for i in 1..size {
+        let mut bit = size >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            real.swap(i, j);
+            imaginary.swap(i, j);
+        }
+    }
+
+    // Combine ever larger butterflies.
+    let mut length = 2;
+    while length <= size {
+        let angle = -2.0 * PI / length as f32;
+        for start in (0..size).step_by(length) {
+            for k in 0..length / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + length / 2;
+                let twiddled_real = real[b] * cos - imaginary[b] * sin;
+                let twiddled_imaginary = real[b] * sin + imaginary[b] * cos;
+                real[b] = real[a] - twiddled_real;
+                imaginary[b] = imaginary[a] - twiddled_imaginary;
+                real[a] += twiddled_real;
+                imaginary[a] += twiddled_imaginary;
+            }
+        }
+        length <<= 1;
+    }
+}
+
+// Levels of a power of two number of samples in log spaced bands, scaled from silence at 0 to full scale at 1.
+fn band_levels(samples: &[f32], sample_rate: u32) -> [f32; BAND_COUNT] {
+    let size = samples.len();
+
+    // A Hann window keeps the edges of the window from smearing across the bands.
+    let mut real: Vec<f32> = samples
+        .iter()
+        .enumerate()
+        .map(|(index, sample)| sample * (0.5 - 0.5 * (2.0 * PI * index as f32 / size as f32).cos()))
+        .collect();
+    let mut imaginary = vec![0.0; size];
+    fft(&mut real, &mut imaginary);
+
+    let bin_width = sample_rate as f32 / size as f32;
+    let edge =
+        |band: usize| LOWEST_FREQUENCY * (HIGHEST_FREQUENCY / LOWEST_FREQUENCY).powf(band as f32 / BAND_COUNT as f32);
+
+    let mut levels = [0.0; BAND_COUNT];
+    for (band, level) in levels.iter_mut().enumerate() {
+        let low = ((edge(band) / bin_width) as usize).max(1);
+        let high = ((edge(band + 1) / bin_width) as usize).max(low + 1).min(size / 2);
+        // Bands above what the sample rate can hold stay silent.
+        if low >= high {
+            continue;
+        }
+
+        // Amplitude of the loudest bin, corrected for the window halving everything.
+        let amplitude = (low..high)
+            .map(|bin| real[bin].hypot(imaginary[bin]) * 4.0 / size as f32)
+            .fold(0.0, f32::max);
+        let decibels = 20.0 * amplitude.max(f32::MIN_POSITIVE).log10();
+        *level = ((decibels + SPECTRUM_FLOOR) / SPECTRUM_FLOOR).clamp(0.0, 1.0);
+    }
+    levels
+}
+
+// Feed the analysed music through beat detection a block at a time, and keep the spectrum up to date.
+fn handle_analysis(
+    time: Res<Time>,
+    streams: Res<Assets<StreamedAudio>>,
+    songs: Res<Assets<Song>>,
+    mut analysis: ResMut<MusicAnalysis>,
+    mut spectrum: ResMut<Spectrum>,
+    mut beat_events: MessageWriter<Beat>,
+    players: Query<
+        (
+            Option<&AudioPlayer<StreamedAudio>>,
+            Option<&AudioPlayer<Song>>,
+            &SpatialAudioSink,
+        ),
+        With<Analysed>,
+    >,
+) {
+    let tap = players
+        .iter()
+        .find(|(_, _, sink)| !sink.is_paused())
+        .and_then(|(stream, song, _)| match (stream, song) {
+            (Some(stream), _) => streams.get(&stream.0).map(StreamedAudio::tap),
+            (_, Some(song)) => songs.get(&song.0).map(Song::tap),
+            _ => None,
+        });
+    let (samples, sample_rate) = tap.map_or((Vec::new(), 0), Tap::drain);
+
+    // Levels rise straight away and fall back slowly, to nothing once the music stops. They hold while the
+    // music waits on more samples, and the spectrum is only touched when they move.
+    let analysis = &mut *analysis;
+    if tap.is_none() || !samples.is_empty() {
+        let mut levels = [0.0; BAND_COUNT];
+        analysis.window.extend(&samples);
+        let excess = analysis.window.len().saturating_sub(FFT_SIZE);
+        analysis.window.drain(..excess);
+        if tap.is_some() && sample_rate > 0 && analysis.window.len() == FFT_SIZE {
+            levels = band_levels(analysis.window.make_contiguous(), sample_rate);
+        }
+        let fall = SPECTRUM_FALL * time.delta_secs();
+        let mut next = spectrum.0;
+        for (current, level) in next.iter_mut().zip(levels) {
+            *current = level.max(*current - fall).max(0.0);
+        }
+        spectrum.set_if_neq(Spectrum(next));
+    }
+
+    for sample in samples {
+        analysis.block.push(sample);
+        if analysis.block.len() < BLOCK_SIZE {
+            continue;
+        }
+
+        let block_energy = energy(&analysis.block);
+        analysis.block.clear();
+        analysis.since_beat += BLOCK_SIZE as f32 / sample_rate as f32;
+
+        if analysis.since_beat >= MIN_BEAT_GAP && is_onset(block_energy, &analysis.history) {
+            analysis.since_beat = 0.0;
+            beat_events.write(Beat);
+        }
+
+        analysis.history.push_back(block_energy);
+        if analysis.history.len() > HISTORY_LENGTH {
+            analysis.history.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A block of a tone at the given amplitude.
+    fn tone(amplitude: f32) -> Vec<f32> {
+        (0..BLOCK_SIZE)
+            .map(|index| amplitude * (2.0 * PI * 440.0 * index as f32 / 44_100.0).sin())
+            .collect()
+    }
+
+    #[test]
+    fn steady_tone_is_not_an_onset() {
+        let block = energy(&tone(0.5));
+        let history: VecDeque<f32> = std::iter::repeat_n(block, HISTORY_LENGTH).collect();
+        assert!(!is_onset(block, &history));
+    }
+
+    #[test]
+    fn click_after_silence_is_an_onset() {
+        let history: VecDeque<f32> = std::iter::repeat_n(0.0, HISTORY_LENGTH).collect();
+        let mut click = vec![0.0; BLOCK_SIZE];
+        click[..8].fill(0.8);
+        assert!(is_onset(energy(&click), &history));
+    }
+
+    #[test]
+    fn needs_a_full_history() {
+        let history: VecDeque<f32> = std::iter::repeat_n(0.0, HISTORY_LENGTH - 1).collect();
+        assert!(!is_onset(energy(&tone(0.5)), &history));
+    }
+
+    #[test]
+    fn impulse_has_a_flat_spectrum() {
+        let mut real = vec![0.0; 16];
+        let mut imaginary = vec![0.0; 16];
+        real[0] = 1.0;
+        fft(&mut real, &mut imaginary);
+        for (real, imaginary) in real.iter().zip(&imaginary) {
+            assert!((real - 1.0).abs() < 1e-5 && imaginary.abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn sine_peaks_in_its_band() {
+        let samples: Vec<f32> = (0..FFT_SIZE)
+            .map(|index| (2.0 * PI * 1000.0 * index as f32 / 44_100.0).sin())
+            .collect();
+        let levels = band_levels(&samples, 44_100);
+        assert!(levels[2] > 0.9, "{levels:?}");
+        for band in [0, 1, 3] {
+            assert!(levels[band] < levels[2], "{levels:?}");
+        }
+    }
+
+    #[test]
+    fn silence_has_no_levels() {
+        assert_eq!(band_levels(&[0.0; FFT_SIZE], 44_100), [0.0; BAND_COUNT]);
+    }
+
+    #[test]
+    fn low_sample_rates_leave_high_bands_silent() {
+        assert_eq!(band_levels(&[0.0; 8], 1000), [0.0; BAND_COUNT]);
+    }
+}
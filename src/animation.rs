@@ -1,7 +1,11 @@
 use bevy::{camera::ScalingMode, prelude::*};
 use std::time::Duration;
 
-use crate::{background, fireplace, house, interaction, snow, stereo, theman, tree};
+use crate::{
+    acoustics, analysis, background, bubble, captions, crackle, dialogue, fireplace, house, interaction, lyrics, mixer,
+    navigation, notice, npc, particles, pet, playlist, settings, snow, soundbank, stereo, stream, surface, synth,
+    theman, tree,
+};
 
 #[derive(Component)]
 pub struct AnimationConfig {
@@ -11,6 +15,17 @@ pub struct AnimationConfig {
     pub frame_timer: Timer,
 }
 
+// The sprite sheets for characters that stand and walk, shared by the man and everyone else.
+#[derive(Clone, Resource)]
+pub struct CharacterSprites {
+    walking_sprite: Handle<Image>,
+    walking_layout: Handle<TextureAtlasLayout>,
+    standing_sprite: Handle<Image>,
+    standing_layout: Handle<TextureAtlasLayout>,
+}
+
+pub const WALKING_LAST_FRAME: usize = 8;
+
 const WINDOW_HEIGHT: f32 = 200.0;
 const WINDOW_WIDTH: f32 = 400.0;
 
@@ -27,17 +42,97 @@ impl AnimationConfig {
     pub fn timer_from_fps(fps: u8) -> Timer {
         Timer::new(Duration::from_secs_f32(1.0 / f32::from(fps)), TimerMode::Once)
     }
+
+    // Move to the next frame once the current one has been shown long enough. After the last frame a looping
+    // animation goes back to the first, otherwise it stays there.
+    pub fn advance(&mut self, delta: Duration, atlas: &mut TextureAtlas, looping: bool) {
+        self.frame_timer.tick(delta);
+
+        if self.frame_timer.just_finished() {
+            if atlas.index == self.last_index {
+                if looping {
+                    atlas.index = self.first_index;
+                }
+            } else {
+                atlas.index += 1;
+            }
+            self.frame_timer = Self::timer_from_fps(self.fps);
+        }
+    }
+}
+
+impl FromWorld for CharacterSprites {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let walking_sprite = asset_server.load("theman/theman_walking_animation.png");
+        let standing_sprite = asset_server.load("theman/theman_standing.png");
+
+        let mut texture_layouts = world.resource_mut::<Assets<TextureAtlasLayout>>();
+        CharacterSprites {
+            walking_sprite,
+            walking_layout: texture_layouts.add(TextureAtlasLayout::from_grid(
+                UVec2::splat(32),
+                WALKING_LAST_FRAME as u32 + 1,
+                1,
+                None,
+                None,
+            )),
+            standing_sprite,
+            standing_layout: texture_layouts.add(TextureAtlasLayout::from_grid(UVec2::splat(32), 1, 1, None, None)),
+        }
+    }
+}
+
+impl CharacterSprites {
+    // Show a character standing still, facing left or right.
+    pub fn stand(&self, sprite: &mut Sprite, facing_left: bool) {
+        sprite.image = self.standing_sprite.clone();
+        sprite.texture_atlas = Some(TextureAtlas {
+            layout: self.standing_layout.clone(),
+            index: 0,
+        });
+        sprite.flip_x = facing_left;
+    }
+
+    // Show a character at the start of their walk cycle, facing left or right.
+    pub fn walk(&self, sprite: &mut Sprite, facing_left: bool) {
+        sprite.image = self.walking_sprite.clone();
+        sprite.texture_atlas = Some(TextureAtlas {
+            layout: self.walking_layout.clone(),
+            index: 0,
+        });
+        sprite.flip_x = facing_left;
+    }
 }
 
 // Add the animation systems.
 pub fn add_systems(app: &mut App) {
-    app.add_systems(Startup, init);
+    app.init_resource::<CharacterSprites>().add_systems(Startup, init);
+    acoustics::add_systems(app);
+    analysis::add_systems(app);
     background::add_systems(app);
+    bubble::add_systems(app);
+    captions::add_systems(app);
+    crackle::add_systems(app);
+    dialogue::add_systems(app);
     interaction::add_systems(app);
+    lyrics::add_systems(app);
+    mixer::add_systems(app);
+    navigation::add_systems(app);
+    notice::add_systems(app);
+    npc::add_systems(app);
+    particles::add_systems(app);
+    pet::add_systems(app);
+    playlist::add_systems(app);
     house::add_systems(app);
     fireplace::add_systems(app);
+    settings::add_systems(app);
     snow::add_systems(app);
+    soundbank::add_systems(app);
     stereo::add_systems(app);
+    stream::add_systems(app);
+    surface::add_systems(app);
+    synth::add_systems(app);
     theman::add_systems(app);
     tree::add_systems(app);
 }
@@ -0,0 +1,190 @@
+use bevy::{
+    prelude::*,
+    sprite::{BorderRect, SliceScaleMode, TextureSlicer},
+    ui::widget::NodeImageMode,
+};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BubbleStyle {
+    Speech,
+    Thought,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum BubbleContent {
+    Text(String),
+    // Path to an image asset, like a music note.
+    Icon(&'static str),
+}
+
+// Message sent to pop a bubble above an entity, queued behind any bubble it's already showing.
+#[derive(Message)]
+pub struct BubbleRequest {
+    pub entity: Entity,
+    pub content: BubbleContent,
+    pub style: BubbleStyle,
+    pub duration: f32,
+}
+
+// Bubbles waiting for their entity's current bubble to go away.
+#[derive(Default, Resource)]
+struct BubbleQueues(HashMap<Entity, VecDeque<BubbleRequest>>);
+
+// A bubble on screen, following the entity it belongs to.
+#[derive(Component)]
+struct Bubble {
+    target: Entity,
+    content: BubbleContent,
+    timer: Timer,
+}
+
+const FONT_SIZE: f32 = 16.0;
+const ICON_SIZE: f32 = 27.0;
+const SLICE_BORDER: f32 = 4.0;
+const TEXT_COLOR: Color = Color::srgb(0.12, 0.1, 0.14);
+
+// How far above the entity's origin the bubble sits, in world units.
+const HEIGHT: f32 = 30.0;
+
+// Add the bubble systems.
+pub fn add_systems(app: &mut App) {
+    app.add_message::<BubbleRequest>()
+        .init_resource::<BubbleQueues>()
+        .add_systems(Update, (handle_requests, handle_show, handle_expire).chain())
+        .add_systems(PostUpdate, handle_follow.after(TransformSystems::Propagate));
+}
+
+// Queue up requested bubbles, skipping repeats of one already waiting or showing.
+fn handle_requests(
+    mut events: MessageReader<BubbleRequest>,
+    mut queues: ResMut<BubbleQueues>,
+    bubbles: Query<&Bubble>,
+) {
+    for event in events.read() {
+        let showing = bubbles
+            .iter()
+            .any(|bubble| bubble.target == event.entity && bubble.content == event.content);
+        let queue = queues.0.entry(event.entity).or_default();
+        if showing || queue.iter().any(|queued| queued.content == event.content) {
+            continue;
+        }
+
+        queue.push_back(BubbleRequest {
+            entity: event.entity,
+            content: event.content.clone(),
+            style: event.style,
+            duration: event.duration,
+        });
+    }
+}
+
+// Show the next queued bubble for any entity that isn't showing one.
+fn handle_show(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut queues: ResMut<BubbleQueues>,
+    bubbles: Query<&Bubble>,
+    targets: Query<(), With<Transform>>,
+) {
+    // Forget about entities that have gone away.
+    queues
+        .0
+        .retain(|entity, queue| !queue.is_empty() && targets.contains(*entity));
+
+    for (entity, queue) in &mut queues.0 {
+        if bubbles.iter().any(|bubble| bubble.target == *entity) {
+            continue;
+        }
+        let Some(request) = queue.pop_front() else {
+            continue;
+        };
+
+        let background = match request.style {
+            BubbleStyle::Speech => "ui/bubble.png",
+            BubbleStyle::Thought => "ui/thought.png",
+        };
+
+        // Start hidden until it's been placed above the entity.
+        let mut bubble = commands.spawn((
+            Bubble {
+                target: *entity,
+                content: request.content.clone(),
+                timer: Timer::from_seconds(request.duration, TimerMode::Once),
+            },
+            ImageNode::new(asset_server.load(background)).with_mode(NodeImageMode::Sliced(TextureSlicer {
+                border: BorderRect::all(SLICE_BORDER),
+                center_scale_mode: SliceScaleMode::Stretch,
+                sides_scale_mode: SliceScaleMode::Stretch,
+                max_corner_scale: 3.0,
+            })),
+            Node {
+                position_type: PositionType::Absolute,
+                padding: UiRect::axes(px(14), px(8)),
+                ..default()
+            },
+            Visibility::Hidden,
+        ));
+
+        match request.content {
+            BubbleContent::Text(text) => {
+                bubble.with_child((
+                    Text::new(text),
+                    TextFont::from_font_size(FONT_SIZE),
+                    TextColor(TEXT_COLOR),
+                ));
+            }
+            BubbleContent::Icon(path) => {
+                bubble.with_child((
+                    ImageNode::new(asset_server.load(path)),
+                    Node {
+                        width: px(ICON_SIZE),
+                        height: px(ICON_SIZE * 11. / 9.),
+                        ..default()
+                    },
+                ));
+            }
+        }
+    }
+}
+
+// Dismiss bubbles once their time is up.
+fn handle_expire(mut commands: Commands, time: Res<Time>, mut bubbles: Query<(Entity, &mut Bubble)>) {
+    for (entity, mut bubble) in &mut bubbles {
+        bubble.timer.tick(time.delta());
+        if bubble.timer.is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// Keep each bubble centered above its entity on screen.
+fn handle_follow(
+    mut commands: Commands,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    mut bubbles: Query<(Entity, &Bubble, &mut Node, &ComputedNode, &mut Visibility)>,
+    targets: Query<&GlobalTransform>,
+) {
+    let (camera, camera_transform) = camera.into_inner();
+
+    for (entity, bubble, mut node, computed, mut visibility) in &mut bubbles {
+        let Ok(target) = targets.get(bubble.target) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let anchor = target.translation() + Vec3::Y * HEIGHT;
+        let Ok(position) = camera.world_to_viewport(camera_transform, anchor) else {
+            continue;
+        };
+
+        let size = computed.size() * computed.inverse_scale_factor();
+        node.left = px(position.x - size.x / 2.0);
+        node.top = px(position.y - size.y);
+
+        // Wait for the layout to size the bubble before showing it.
+        if size != Vec2::ZERO {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use bevy::{audio::AudioSinkPlayback, prelude::*};
+
+use crate::settings::Settings;
+
+// Add to an audio player to describe it in closed captions while it plays.
+#[derive(Component)]
+pub struct Caption(pub String);
+
+// Captions on screen, kept up a little after their sound stops so short sounds can be read.
+#[derive(Default, Resource)]
+struct ShownCaptions(Vec<ShownCaption>);
+
+struct ShownCaption {
+    text: String,
+    // Which side the sound is coming from, if it's off to one side.
+    side: Option<Side>,
+    // Seconds left before it's cleared.
+    remaining: f32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+// The text listing the captions.
+#[derive(Component)]
+struct CaptionText;
+
+// A captioned sound, with where it is and whichever sink it plays through.
+type CaptionedSound<'a> = (
+    &'a Caption,
+    Option<&'a GlobalTransform>,
+    Option<&'a AudioSink>,
+    Option<&'a SpatialAudioSink>,
+);
+
+const CAPTION_HOLD: f32 = 1.0;
+const CAPTION_SIZE: f32 = 14.0;
+// Sounds closer than this across from the listener are straight ahead.
+const CENTER_WIDTH: f32 = 24.0;
+
+// Add the caption systems.
+pub fn add_systems(app: &mut App) {
+    app.init_resource::<ShownCaptions>()
+        .add_systems(Startup, init)
+        .add_systems(Update, handle_captions);
+}
+
+// Caption the sounds playing, pointing out which side they're on.
+fn handle_captions(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut shown: ResMut<ShownCaptions>,
+    listener: Single<&GlobalTransform, With<SpatialListener>>,
+    sounds: Query<CaptionedSound>,
+    caption_text: Single<(&mut Text, &mut Visibility), With<CaptionText>>,
+) {
+    let (mut text, mut visibility) = caption_text.into_inner();
+    if !settings.captions {
+        shown.0.clear();
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+
+    for caption in &mut shown.0 {
+        caption.remaining -= time.delta_secs();
+    }
+    shown.0.retain(|caption| caption.remaining > 0.0);
+
+    for (caption, transform, sink, spatial_sink) in &sounds {
+        let playing = sink.is_some_and(|sink| !sink.is_paused() && !sink.empty())
+            || spatial_sink.is_some_and(|sink| !sink.is_paused() && !sink.empty());
+        if !playing {
+            continue;
+        }
+
+        // Only spatial sounds come from a side.
+        let offset = transform
+            .filter(|_| spatial_sink.is_some())
+            .map_or(0.0, |transform| transform.translation().x - listener.translation().x);
+        let side = if offset < -CENTER_WIDTH {
+            Some(Side::Left)
+        } else if offset > CENTER_WIDTH {
+            Some(Side::Right)
+        } else {
+            None
+        };
+
+        // The same sound from several places shows once, from wherever was heard last.
+        match shown.0.iter_mut().find(|shown| shown.text == caption.0) {
+            Some(shown) => {
+                shown.side = side;
+                shown.remaining = CAPTION_HOLD;
+            }
+            None => shown.0.push(ShownCaption {
+                text: caption.0.clone(),
+                side,
+                remaining: CAPTION_HOLD,
+            }),
+        }
+    }
+
+    let captions = shown
+        .0
+        .iter()
+        .map(|caption| match caption.side {
+            Some(Side::Left) => format!("[< {}]", caption.text),
+            Some(Side::Right) => format!("[{} >]", caption.text),
+            None => format!("[{}]", caption.text),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.0 != captions {
+        text.0 = captions;
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+}
+
+// Create the captions in the bottom left corner, shown when turned on in the settings.
+fn init(mut commands: Commands) {
+    commands.spawn((
+        CaptionText,
+        Text::default(),
+        TextFont::from_font_size(CAPTION_SIZE),
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: px(12),
+            left: px(12),
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
@@ -0,0 +1,188 @@
+use bevy::{
+    audio::{AddAudioSource, Decodable, Source},
+    prelude::*,
+};
+use rand::{Rng, SeedableRng, rng, rngs::SmallRng};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::{
+    acoustics::{Acoustic, AcousticControls, Acoustics},
+    fireplace::Intensity,
+};
+
+// The sound of a fire, made up as it plays so it never repeats. Put it on an entity with an `Intensity`
+// and it burns as strongly as that does.
+//
+// The fire never ends by itself, so play it once rather than looping it, which would hold on to
+// everything it's played so far.
+#[derive(Asset, Default, TypePath)]
+pub struct FireSound {
+    // How strongly the fire is burning, as the bits of an f32.
+    intensity: Arc<AtomicU32>,
+    acoustics: AcousticControls,
+}
+
+// Plays a fire, a sample at a time.
+pub struct Crackle {
+    intensity: Arc<AtomicU32>,
+    rng: SmallRng,
+    // The intensity heard, following the one set so changes don't click.
+    level: f32,
+    // Low passed noise for the rush of the flames, and how loud it is swelling towards.
+    bed: f32,
+    swell: f32,
+    swell_target: f32,
+    sparks: Vec<Spark>,
+}
+
+// A crackle or pop dying away.
+struct Spark {
+    amplitude: f32,
+    // How much is left of the amplitude each sample.
+    decay: f32,
+    // Pops are a low tone, crackles are a burst of noise.
+    frequency: Option<f32>,
+    phase: f32,
+}
+
+const SAMPLE_RATE: u32 = 22_050;
+// How quickly the heard intensity follows the fire, per sample.
+const LEVEL_SMOOTHING: f32 = 0.0005;
+
+// The bed gets brighter as well as louder as the fire grows.
+const BED_VOLUME: f32 = 1.6;
+const BED_DULL: f32 = 0.03;
+const BED_BRIGHT: f32 = 0.12;
+// The flames surge and settle every so often.
+const SWELL_CHANGES: f32 = 3.0;
+const SWELL_SMOOTHING: f32 = 0.0003;
+
+// Per second, at full intensity.
+const CRACKLE_RATE: f32 = 30.0;
+const POP_RATE: f32 = 0.8;
+// New sparks are skipped while this many are playing, they'd only be lost in the noise.
+const MAX_SPARKS: usize = 24;
+
+// Add the fire sound systems.
+pub fn add_systems(app: &mut App) {
+    app.add_audio_source::<FireSound>()
+        .add_systems(Update, handle_intensity);
+}
+
+impl Decodable for FireSound {
+    type DecoderItem = f32;
+    type Decoder = Acoustics<Crackle>;
+
+    fn decoder(&self) -> Self::Decoder {
+        let crackle = Crackle {
+            intensity: self.intensity.clone(),
+            rng: SmallRng::from_rng(&mut rng()),
+            level: 0.0,
+            bed: 0.0,
+            swell: 1.0,
+            swell_target: 1.0,
+            sparks: Vec::with_capacity(MAX_SPARKS),
+        };
+        Acoustics::new(crackle, self.acoustics.clone())
+    }
+}
+
+impl Acoustic for FireSound {
+    fn acoustics(&self) -> &AcousticControls {
+        &self.acoustics
+    }
+
+    // Copies burn along with the same fire.
+    fn instance(&self) -> Self {
+        FireSound {
+            intensity: self.intensity.clone(),
+            acoustics: AcousticControls::default(),
+        }
+    }
+}
+
+impl Crackle {
+    // Maybe start a spark, happening `rate` times a second on average.
+    fn spark(&mut self, rate: f32, duration: (f32, f32), frequency: Option<(f32, f32)>) {
+        if self.sparks.len() >= MAX_SPARKS || self.rng.random::<f32>() >= rate / SAMPLE_RATE as f32 {
+            return;
+        }
+
+        let duration = self.rng.random_range(duration.0..duration.1);
+        self.sparks.push(Spark {
+            amplitude: self.rng.random_range(0.1..0.6) * (0.5 + 0.5 * self.level),
+            decay: (-1.0 / (duration * SAMPLE_RATE as f32)).exp(),
+            frequency: frequency.map(|(low, high)| self.rng.random_range(low..high)),
+            phase: 0.0,
+        });
+    }
+}
+
+impl Iterator for Crackle {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let intensity = f32::from_bits(self.intensity.load(Ordering::Relaxed));
+        self.level += (intensity - self.level) * LEVEL_SMOOTHING;
+
+        let noise = self.rng.random_range(-1.0..1.0);
+        self.bed += (noise - self.bed) * (BED_DULL + (BED_BRIGHT - BED_DULL) * self.level);
+        if self.rng.random::<f32>() < SWELL_CHANGES / SAMPLE_RATE as f32 {
+            self.swell_target = self.rng.random_range(0.5..1.0);
+        }
+        self.swell += (self.swell_target - self.swell) * SWELL_SMOOTHING;
+
+        self.spark(CRACKLE_RATE * self.level, (0.002, 0.012), None);
+        self.spark(POP_RATE * self.level, (0.02, 0.06), Some((120.0, 400.0)));
+
+        // Crackles take the hiss left over from the bed, so they snap rather than thud.
+        let mut sample = self.bed * BED_VOLUME * self.swell * self.level;
+        for spark in &mut self.sparks {
+            sample += spark.amplitude
+                * match spark.frequency {
+                    Some(frequency) => {
+                        spark.phase = (spark.phase + frequency / SAMPLE_RATE as f32).fract();
+                        (spark.phase * TAU).sin()
+                    }
+                    None => noise - self.bed,
+                };
+            spark.amplitude *= spark.decay;
+        }
+        self.sparks.retain(|spark| spark.amplitude > 0.001);
+
+        Some(sample.clamp(-1.0, 1.0))
+    }
+}
+
+impl Source for Crackle {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+// Pass the fire's intensity on to its sound.
+fn handle_intensity(
+    fire_sounds: Res<Assets<FireSound>>,
+    query: Query<(&Intensity, &AudioPlayer<FireSound>), Changed<Intensity>>,
+) {
+    for (intensity, player) in &query {
+        if let Some(sound) = fire_sounds.get(&player.0) {
+            sound.intensity.store(intensity.0.to_bits(), Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,634 @@
+use bevy::{
+    asset::LoadContext,
+    ecs::system::SystemParam,
+    prelude::*,
+    sprite::{BorderRect, SliceScaleMode, TextureSlicer},
+    ui::widget::NodeImageMode,
+};
+use std::collections::{HashMap, HashSet};
+
+use crate::interaction::{InteractionEvent, Verb};
+use crate::text_asset::{TextAsset, TextAssetError, TextAssetLoader};
+
+// A conversation loaded from a `.dialogue` file.
+//
+// Files are made of `[node]` sections. Inside a node, `speaker: text` lines are spoken, other lines are
+// narration, `? text -> node` offers a choice (`? [flag] text -> node` only when the flag is set),
+// `! interact id`, `! set flag` and `! clear flag` run when the node starts, and `-> node` continues on
+// once the lines are done. Interactions use the thing unless given another verb, like
+// `! interact stereo skip`. Conversations begin at `[start]`.
+#[derive(Asset, TypePath)]
+pub struct Dialogue {
+    nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Default)]
+struct DialogueNode {
+    lines: Vec<Line>,
+    choices: Vec<Choice>,
+    actions: Vec<Action>,
+    next: Option<String>,
+}
+
+struct Line {
+    speaker: Option<String>,
+    text: String,
+}
+
+struct Choice {
+    text: String,
+    target: String,
+    flag: Option<String>,
+}
+
+enum Action {
+    Interact(String, Verb),
+    Set(String),
+    Clear(String),
+}
+
+// Flags set by conversations, for later conversations to branch on.
+#[derive(Default, Resource)]
+pub struct DialogueFlags(pub HashSet<String>);
+
+// The conversation currently on screen, if any.
+#[derive(Default, Resource)]
+pub struct DialogueState {
+    conversation: Option<Conversation>,
+}
+
+struct Conversation {
+    dialogue: Handle<Dialogue>,
+    node: Option<String>,
+    line: usize,
+    choices: Vec<String>,
+    selected: usize,
+}
+
+// What a node's actions can change when it starts.
+#[derive(SystemParam)]
+struct NodeActions<'w> {
+    flags: ResMut<'w, DialogueFlags>,
+    interaction_events: MessageWriter<'w, InteractionEvent>,
+}
+
+#[derive(Component)]
+struct DialogueBox;
+
+#[derive(Component)]
+struct SpeakerText;
+
+// Reveals the body text a few characters at a time.
+#[derive(Component)]
+struct Typewriter {
+    line: Option<(String, usize)>,
+    text: String,
+    shown: f32,
+}
+
+#[derive(Component)]
+struct ChoiceText {
+    node: String,
+    index: usize,
+}
+
+const START_NODE: &str = "start";
+
+const CHARACTERS_PER_SECOND: f32 = 40.0;
+const FONT_SIZE: f32 = 22.0;
+const SLICE_BORDER: f32 = 8.0;
+
+const CHOICE_COLOR: Color = Color::srgb(0.75, 0.75, 0.8);
+const SELECTED_COLOR: Color = Color::srgb(1.0, 0.85, 0.4);
+
+// Add the dialogue systems.
+pub fn add_systems(app: &mut App) {
+    app.init_asset::<Dialogue>()
+        .init_asset_loader::<TextAssetLoader<Dialogue>>()
+        .init_resource::<DialogueFlags>()
+        .init_resource::<DialogueState>()
+        .add_systems(
+            Update,
+            (
+                handle_interaction,
+                handle_start,
+                handle_keys,
+                handle_typewriter,
+                handle_choices,
+            )
+                .chain(),
+        );
+}
+
+// Run condition for systems that should pause while a conversation is on screen.
+pub fn is_open(state: Res<DialogueState>) -> bool {
+    state.conversation.is_some()
+}
+
+impl TextAsset for Dialogue {
+    const EXTENSIONS: &'static [&'static str] = &["dialogue"];
+
+    fn from_text(source: &str, _load_context: &mut LoadContext<'_>) -> Result<Self, TextAssetError> {
+        Dialogue::parse(source)
+    }
+}
+
+impl DialogueNode {
+    // Choices whose flags are all set.
+    fn available_choices<'a>(&'a self, flags: &'a DialogueFlags) -> impl Iterator<Item = &'a Choice> {
+        self.choices
+            .iter()
+            .filter(|choice| choice.flag.as_ref().is_none_or(|flag| flags.0.contains(flag)))
+    }
+}
+
+impl Dialogue {
+    // Parse the text of a `.dialogue` file.
+    pub fn parse(source: &str) -> Result<Self, TextAssetError> {
+        let mut nodes = HashMap::new();
+        let mut current: Option<(String, DialogueNode)> = None;
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: &str| TextAssetError::at(index + 1, message);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // A new node ends the previous one.
+            if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                if let Some((name, node)) = current.take() {
+                    nodes.insert(name, node);
+                }
+                current = Some((name.trim().to_string(), DialogueNode::default()));
+                continue;
+            }
+
+            let Some((_, node)) = &mut current else {
+                return Err(error("expected a [node] before any lines"));
+            };
+
+            if let Some(rest) = line.strip_prefix('?') {
+                let (text, target) = rest
+                    .split_once("->")
+                    .ok_or_else(|| error("choice is missing '-> node'"))?;
+                let text = text.trim();
+                let (flag, text) = match text.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+                    Some((flag, text)) => (Some(flag.trim().to_string()), text.trim()),
+                    None => (None, text),
+                };
+                node.choices.push(Choice {
+                    text: text.to_string(),
+                    target: target.trim().to_string(),
+                    flag,
+                });
+            } else if let Some(rest) = line.strip_prefix('!') {
+                let (command, argument) = rest
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| error("action is missing an argument"))?;
+                let argument = argument.trim();
+                node.actions.push(match command {
+                    "interact" => {
+                        let (id, verb) = match argument.split_once(char::is_whitespace) {
+                            Some((id, verb)) => (id, parse_verb(verb.trim()).ok_or_else(|| error("unknown verb"))?),
+                            None => (argument, Verb::Use),
+                        };
+                        Action::Interact(id.to_string(), verb)
+                    }
+                    "set" => Action::Set(argument.to_string()),
+                    "clear" => Action::Clear(argument.to_string()),
+                    _ => return Err(error("unknown action")),
+                });
+            } else if let Some(target) = line.strip_prefix("->") {
+                node.next = Some(target.trim().to_string());
+            } else {
+                // Single word before a colon is a speaker, anything else is narration.
+                let line = match line.split_once(": ") {
+                    Some((speaker, text)) if !speaker.contains(char::is_whitespace) => Line {
+                        speaker: Some(speaker.to_string()),
+                        text: text.to_string(),
+                    },
+                    _ => Line {
+                        speaker: None,
+                        text: line.to_string(),
+                    },
+                };
+                node.lines.push(line);
+            }
+        }
+
+        if let Some((name, node)) = current.take() {
+            nodes.insert(name, node);
+        }
+
+        // Make sure every branch leads somewhere.
+        if !nodes.contains_key(START_NODE) {
+            return Err(TextAssetError::at(0, format!("missing [{START_NODE}] node")));
+        }
+        for node in nodes.values() {
+            let targets = node
+                .choices
+                .iter()
+                .map(|choice| &choice.target)
+                .chain(node.next.as_ref());
+            for target in targets {
+                if !nodes.contains_key(target) {
+                    return Err(TextAssetError::at(0, format!("unknown node [{target}]")));
+                }
+            }
+        }
+
+        Ok(Self { nodes })
+    }
+}
+
+// Read a verb named in an interact action.
+fn parse_verb(source: &str) -> Option<Verb> {
+    match source {
+        "use" => Some(Verb::Use),
+        "examine" => Some(Verb::Examine),
+        "skip" => Some(Verb::Skip),
+        "poke" => Some(Verb::Poke),
+        _ => None,
+    }
+}
+
+// Start a conversation when talking to a character or examining a prop.
+fn handle_interaction(
+    asset_server: Res<AssetServer>,
+    mut state: ResMut<DialogueState>,
+    mut events: MessageReader<InteractionEvent>,
+) {
+    for event in events.read() {
+        if state.conversation.is_some() {
+            continue;
+        }
+
+        let path = match (event.id.strip_prefix("npc:"), event.verb) {
+            (Some(name), _) => format!("dialogue/{name}.dialogue"),
+            (None, Verb::Examine) => format!("dialogue/examine/{}.dialogue", event.id),
+            (None, Verb::Use | Verb::Skip | Verb::Poke) => continue,
+        };
+
+        state.conversation = Some(Conversation {
+            dialogue: asset_server.load(path),
+            node: None,
+            line: 0,
+            choices: vec![],
+            selected: 0,
+        });
+    }
+}
+
+// Open the text box once the conversation has loaded.
+fn handle_start(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    dialogues: Res<Assets<Dialogue>>,
+    mut state: ResMut<DialogueState>,
+    mut actions: NodeActions,
+) {
+    let Some(conversation) = &mut state.conversation else {
+        return;
+    };
+    if conversation.node.is_some() {
+        return;
+    }
+
+    if asset_server.load_state(&conversation.dialogue).is_failed() {
+        state.conversation = None;
+        return;
+    }
+    let Some(dialogue) = dialogues.get(&conversation.dialogue) else {
+        return;
+    };
+
+    // Create the text box along the bottom of the screen.
+    commands.spawn((
+        DialogueBox,
+        ImageNode::new(asset_server.load("ui/textbox.png")).with_mode(NodeImageMode::Sliced(TextureSlicer {
+            border: BorderRect::all(SLICE_BORDER),
+            center_scale_mode: SliceScaleMode::Stretch,
+            sides_scale_mode: SliceScaleMode::Stretch,
+            max_corner_scale: 4.0,
+        })),
+        Node {
+            position_type: PositionType::Absolute,
+            left: percent(5),
+            right: percent(5),
+            bottom: percent(4),
+            min_height: percent(24),
+            padding: UiRect::all(px(28)),
+            flex_direction: FlexDirection::Column,
+            row_gap: px(8),
+            ..default()
+        },
+        children![
+            (
+                SpeakerText,
+                Text::default(),
+                TextFont::from_font_size(FONT_SIZE),
+                TextColor(SELECTED_COLOR),
+            ),
+            (
+                Typewriter {
+                    line: None,
+                    text: String::new(),
+                    shown: 0.0,
+                },
+                Text::default(),
+                TextFont::from_font_size(FONT_SIZE),
+            ),
+        ],
+    ));
+
+    enter_node(conversation, dialogue, START_NODE, &mut actions);
+}
+
+// Move the conversation to a node and run its actions.
+fn enter_node(conversation: &mut Conversation, dialogue: &Dialogue, name: &str, actions: &mut NodeActions) {
+    conversation.node = Some(name.to_string());
+    conversation.line = 0;
+    conversation.choices.clear();
+    conversation.selected = 0;
+
+    let Some(node) = dialogue.nodes.get(name) else {
+        return;
+    };
+    for action in &node.actions {
+        match action {
+            Action::Interact(id, verb) => {
+                actions.interaction_events.write(InteractionEvent {
+                    id: id.clone(),
+                    verb: *verb,
+                });
+            }
+            Action::Set(flag) => {
+                actions.flags.0.insert(flag.clone());
+            }
+            Action::Clear(flag) => {
+                actions.flags.0.remove(flag);
+            }
+        }
+    }
+}
+
+// Advance the conversation and pick choices with the keyboard.
+fn handle_keys(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    dialogues: Res<Assets<Dialogue>>,
+    mut state: ResMut<DialogueState>,
+    mut actions: NodeActions,
+    mut typewriter: Query<&mut Typewriter>,
+    dialogue_box: Query<Entity, With<DialogueBox>>,
+) {
+    let Some(conversation) = &mut state.conversation else {
+        return;
+    };
+    let Some(dialogue) = dialogues.get(&conversation.dialogue) else {
+        return;
+    };
+    let Some(node) = conversation.node.as_ref().and_then(|name| dialogue.nodes.get(name)) else {
+        return;
+    };
+
+    // Move the choice selection.
+    if !conversation.choices.is_empty() {
+        if keyboard.just_pressed(KeyCode::ArrowUp) {
+            conversation.selected = conversation.selected.saturating_sub(1);
+        } else if keyboard.just_pressed(KeyCode::ArrowDown) {
+            conversation.selected = (conversation.selected + 1).min(conversation.choices.len() - 1);
+        }
+    }
+
+    if !keyboard.any_just_pressed([KeyCode::Space, KeyCode::Enter]) {
+        return;
+    }
+
+    // Finish the current line before moving on.
+    if let Ok(mut typewriter) = typewriter.single_mut()
+        && (typewriter.shown as usize) < typewriter.text.chars().count()
+    {
+        typewriter.shown = typewriter.text.chars().count() as f32;
+        return;
+    }
+
+    let mut next = if !conversation.choices.is_empty() {
+        Some(conversation.choices[conversation.selected].clone())
+    } else if conversation.line + 1 < node.lines.len() {
+        conversation.line += 1;
+        return;
+    } else {
+        node.next.clone()
+    };
+
+    // Pass straight through nodes with nothing to show, without looping forever.
+    for _ in 0..=dialogue.nodes.len() {
+        let Some(name) = next.take() else {
+            break;
+        };
+        enter_node(conversation, dialogue, &name, &mut actions);
+
+        let node = &dialogue.nodes[&name];
+        if !node.lines.is_empty() || node.available_choices(&actions.flags).next().is_some() {
+            return;
+        }
+        next = node.next.clone();
+    }
+
+    state.conversation = None;
+    for entity in &dialogue_box {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Type out the current line, then offer any choices.
+fn handle_typewriter(
+    time: Res<Time>,
+    dialogues: Res<Assets<Dialogue>>,
+    flags: Res<DialogueFlags>,
+    mut state: ResMut<DialogueState>,
+    mut speaker: Query<&mut Text, (With<SpeakerText>, Without<Typewriter>)>,
+    mut body: Query<(&mut Text, &mut Typewriter), Without<SpeakerText>>,
+) {
+    let Some(conversation) = &mut state.conversation else {
+        return;
+    };
+    let Some(node) = conversation
+        .node
+        .as_ref()
+        .and_then(|name| dialogues.get(&conversation.dialogue)?.nodes.get(name))
+    else {
+        return;
+    };
+    let (Ok(mut speaker), Ok((mut body, mut typewriter))) = (speaker.single_mut(), body.single_mut()) else {
+        return;
+    };
+
+    // Start typing a new line.
+    let key = conversation.node.clone().map(|name| (name, conversation.line));
+    let line = node.lines.get(conversation.line);
+    if typewriter.line != key {
+        typewriter.line = key;
+        typewriter.text = line.map(|line| line.text.clone()).unwrap_or_default();
+        typewriter.shown = 0.0;
+        speaker.0 = line.and_then(|line| line.speaker.clone()).unwrap_or_default();
+    }
+
+    let total = typewriter.text.chars().count();
+    typewriter.shown = (typewriter.shown + CHARACTERS_PER_SECOND * time.delta_secs()).min(total as f32);
+    body.0 = typewriter.text.chars().take(typewriter.shown as usize).collect();
+
+    // Offer the choices once the last line has been typed out.
+    let last_line = conversation.line + 1 >= node.lines.len();
+    if last_line && typewriter.shown as usize >= total && conversation.choices.is_empty() {
+        conversation.choices = node
+            .available_choices(&flags)
+            .map(|choice| choice.target.clone())
+            .collect();
+    }
+}
+
+// Keep the choice list in sync with the conversation.
+fn handle_choices(
+    mut commands: Commands,
+    dialogues: Res<Assets<Dialogue>>,
+    flags: Res<DialogueFlags>,
+    state: Res<DialogueState>,
+    dialogue_box: Query<Entity, With<DialogueBox>>,
+    mut choices: Query<(Entity, &ChoiceText, &mut TextColor)>,
+) {
+    let Some(conversation) = &state.conversation else {
+        return;
+    };
+    let Some(name) = &conversation.node else {
+        return;
+    };
+
+    // Remove the old choices once one is picked or the conversation moves on.
+    let stale = choices.iter().any(|(_, choice, _)| choice.node != *name);
+    if conversation.choices.is_empty() || stale {
+        for (entity, ..) in &choices {
+            commands.entity(entity).despawn();
+        }
+        if conversation.choices.is_empty() {
+            return;
+        }
+    }
+
+    // Highlight the selected choice.
+    if !choices.is_empty() && !stale {
+        for (_, choice, mut color) in &mut choices {
+            color.0 = if choice.index == conversation.selected {
+                SELECTED_COLOR
+            } else {
+                CHOICE_COLOR
+            };
+        }
+        return;
+    }
+
+    let (Ok(dialogue_box), Some(node)) = (
+        dialogue_box.single(),
+        dialogues
+            .get(&conversation.dialogue)
+            .and_then(|dialogue| dialogue.nodes.get(name)),
+    ) else {
+        return;
+    };
+
+    for (index, choice) in node.available_choices(&flags).enumerate() {
+        commands.entity(dialogue_box).with_child((
+            ChoiceText {
+                node: name.clone(),
+                index,
+            },
+            Text::new(format!("> {}", choice.text)),
+            TextFont::from_font_size(FONT_SIZE),
+            TextColor(if index == conversation.selected {
+                SELECTED_COLOR
+            } else {
+                CHOICE_COLOR
+            }),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The line number of a parse error.
+    fn error_line(source: &str) -> usize {
+        match Dialogue::parse(source) {
+            Err(TextAssetError::Parse { line, .. }) => line,
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn parses_nodes() {
+        let dialogue = Dialogue::parse(
+            "# A comment\n\
+             [start]\n\
+             grandma: Hello dear.\n\
+             The fire crackles.\n\
+             ? Hi! -> warm\n\
+             ? [met] Again? -> warm\n\
+             [warm]\n\
+             ! interact fireplace\n\
+             ! interact stereo skip\n\
+             ! set met\n\
+             ! clear cold\n\
+             -> start\n",
+        )
+        .unwrap();
+
+        let start = &dialogue.nodes["start"];
+        assert_eq!(start.lines[0].speaker.as_deref(), Some("grandma"));
+        assert_eq!(start.lines[0].text, "Hello dear.");
+        assert_eq!(start.lines[1].speaker, None);
+        assert_eq!(start.lines[1].text, "The fire crackles.");
+        assert_eq!(start.choices.len(), 2);
+        assert_eq!(start.choices[0].flag, None);
+        assert_eq!(start.choices[1].flag.as_deref(), Some("met"));
+        assert_eq!(start.choices[1].text, "Again?");
+        assert_eq!(start.choices[1].target, "warm");
+
+        let warm = &dialogue.nodes["warm"];
+        assert!(matches!(&warm.actions[0], Action::Interact(id, Verb::Use) if id == "fireplace"));
+        assert!(matches!(&warm.actions[1], Action::Interact(id, Verb::Skip) if id == "stereo"));
+        assert!(matches!(&warm.actions[2], Action::Set(flag) if flag == "met"));
+        assert!(matches!(&warm.actions[3], Action::Clear(flag) if flag == "cold"));
+        assert_eq!(warm.next.as_deref(), Some("start"));
+    }
+
+    #[test]
+    fn flagged_choices_need_their_flag() {
+        let dialogue = Dialogue::parse("[start]\n? Hi -> start\n? [met] Again -> start\n").unwrap();
+        let mut flags = DialogueFlags::default();
+        assert_eq!(dialogue.nodes["start"].available_choices(&flags).count(), 1);
+
+        flags.0.insert("met".to_string());
+        assert_eq!(dialogue.nodes["start"].available_choices(&flags).count(), 2);
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        assert_eq!(error_line("Hello.\n[start]\n"), 1);
+        assert_eq!(error_line("[start]\n\n? Hi there\n"), 3);
+        assert_eq!(error_line("[start]\n! dance\n"), 2);
+        assert_eq!(error_line("[start]\n! interact\n"), 2);
+        assert_eq!(error_line("[start]\n! interact stereo dance\n"), 2);
+    }
+
+    #[test]
+    fn reports_missing_nodes() {
+        assert_eq!(error_line("[other]\nHello.\n"), 0);
+        assert_eq!(error_line("[start]\n-> nowhere\n"), 0);
+        assert_eq!(error_line("[start]\n? Go -> nowhere\n"), 0);
+    }
+}
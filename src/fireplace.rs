@@ -3,11 +3,16 @@ use rand::Rng;
 
 use crate::{
     animation::AnimationConfig,
-    interaction::{Highlight, Interactable, InteractionEvent},
+    captions::Caption,
+    crackle::FireSound,
+    interaction::{Facing, Highlight, Interactable, InteractionEvent, Verb},
+    mixer::{Attenuation, Bus, Fade, FadeCurve},
+    navigation::Obstacle,
+    particles::{ParticleBurst, ParticleEmitter, SpawnShape},
 };
 
 #[derive(Clone, Component, Copy, PartialEq)]
-enum State {
+pub enum State {
     Off,
     Running,
 }
@@ -20,14 +25,47 @@ struct SpriteAssets {
 }
 
 #[derive(Component)]
-struct Fireplace;
+pub struct Fireplace;
+
+// How strongly the fire is burning, from 0 when out to 1 when roaring.
+#[derive(Component, PartialEq)]
+pub struct Intensity(pub f32);
+
+// The emitter for embers drifting up from the fire, more of them the stronger it burns.
+#[derive(Component)]
+struct Embers;
+
+// The emitter for sparks thrown out when the fire is lit or poked.
+#[derive(Component)]
+struct Sparks;
 
 const RUNNING_VOLUME: f32 = 0.9;
+// The fire's sound follows its intensity itself, this only starts and stops it.
+const FIRE_FADE: f32 = 0.25;
+// The fire is a quiet sound, only heard well up close.
+const FIRE_MIN_DISTANCE: f32 = 48.0;
+const FIRE_MAX_DISTANCE: f32 = 320.0;
+const FIRE_ROLLOFF: f32 = 1.0;
+
+// Seconds for a fire to catch fully, and to burn out once put out.
+const KINDLE_TIME: f32 = 4.0;
+const BURN_OUT_TIME: f32 = 2.5;
+const DIM_BRIGHTNESS: f32 = 0.45;
 
 const SPRITE_SCALE: f32 = 2.5;
 const SPRITE_WIDTH: f32 = 8.;
 const SPRITE_HEIGHT: f32 = 16.;
 
+// Where the logs sit, from the middle of the sprite before it's scaled.
+const LOGS_OFFSET: Vec3 = Vec3::new(0.0, -6.0, 0.1);
+// Embers let off every second at full intensity.
+const EMBER_RATE: f32 = 4.0;
+const SPARK_BURST: usize = 14;
+const POKE_BURST: usize = 8;
+
+const OBSTACLE_DEPTH: f32 = 6.0;
+const STAND_OFFSET: f32 = -20.0;
+
 const INTERACTABLE_ID: &str = "fireplace";
 
 // Add the animation systems.
@@ -40,23 +78,30 @@ pub fn add_systems(app: &mut App) {
             handle_highlight_reset,
             handle_interaction,
             handle_interaction_disable_highlight,
-            handle_sound,
+            handle_intensity.after(handle_highlight),
+            handle_embers.after(handle_intensity),
         ),
     );
 }
 
 // Manage the animation frame timing.
-fn handle_animations(time: Res<Time>, mut query: Query<(&mut AnimationConfig, &mut Sprite, &State), With<Fireplace>>) {
+fn handle_animations(
+    time: Res<Time>,
+    mut query: Query<(&mut AnimationConfig, &mut Sprite, &Intensity), With<Fireplace>>,
+) {
     let mut rng = rand::rng();
 
-    for (mut config, mut sprite, state) in &mut query {
-        // Off state only has one frame so skip.
-        if *state == State::Off {
+    for (mut config, mut sprite, intensity) in &mut query {
+        // A burnt out fire only has one frame so skip.
+        if intensity.0 == 0.0 {
             continue;
         }
 
-        // Track how long the current sprite has been displayed.
-        config.frame_timer.tick(time.delta());
+        // Track how long the current sprite has been displayed, weaker fires flicker lazily.
+        config.frame_timer.tick(
+            time.delta()
+                .mul_f32(DIM_BRIGHTNESS + (1.0 - DIM_BRIGHTNESS) * intensity.0),
+        );
 
         if config.frame_timer.just_finished()
             && let Some(atlas) = &mut sprite.texture_atlas
@@ -102,32 +147,54 @@ fn handle_highlight_reset(
     }
 }
 
-// Listen for interaction events and update the state.
+// Listen for interaction events and update the state, throwing out sparks when the fire's lit or poked.
 fn handle_interaction(
     sprite_assets: Res<SpriteAssets>,
     mut events: MessageReader<InteractionEvent>,
-    mut query: Query<(&mut State, &mut Sprite), With<Fireplace>>,
+    mut bursts: MessageWriter<ParticleBurst>,
+    mut query: Query<(&mut State, &mut Sprite, &Intensity), With<Fireplace>>,
+    sparks: Query<Entity, With<Sparks>>,
 ) {
     for event in events.read() {
-        if event.id == INTERACTABLE_ID
-            && let Ok((mut state, mut sprite)) = query.single_mut()
-        {
-            match *state {
-                State::Off => {
-                    *state = State::Running;
-                    sprite.image = sprite_assets.running_sprite.clone();
-                    sprite.texture_atlas = Some(TextureAtlas {
-                        layout: sprite_assets.running_layout.clone(),
-                        index: 0,
+        if event.id != INTERACTABLE_ID {
+            continue;
+        }
+        let Ok((mut state, mut sprite, intensity)) = query.single_mut() else {
+            continue;
+        };
+
+        match (event.verb, *state) {
+            (Verb::Use, State::Off) => {
+                *state = State::Running;
+                sprite.image = sprite_assets.running_sprite.clone();
+                sprite.texture_atlas = Some(TextureAtlas {
+                    layout: sprite_assets.running_layout.clone(),
+                    index: 0,
+                });
+                for emitter in &sparks {
+                    bursts.write(ParticleBurst {
+                        emitter,
+                        count: SPARK_BURST,
                     });
                 }
+            }
 
-                State::Running => {
-                    *state = State::Off;
-                    sprite.image = sprite_assets.off_sprite.clone();
-                    sprite.texture_atlas = None;
+            // The fire keeps burning until it's died down.
+            (Verb::Use, State::Running) => {
+                *state = State::Off;
+            }
+
+            // Only a fire that's still burning has anything to stir up.
+            (Verb::Poke, _) if intensity.0 > 0.0 => {
+                for emitter in &sparks {
+                    bursts.write(ParticleBurst {
+                        emitter,
+                        count: POKE_BURST,
+                    });
                 }
             }
+
+            _ => {}
         }
     }
 }
@@ -142,19 +209,53 @@ fn handle_interaction_disable_highlight(
     }
 }
 
-// Control audio playback based on fireplace state
-fn handle_sound(query: Query<(&State, &mut SpatialAudioSink), (With<Fireplace>, Changed<State>)>) {
-    for (state, audio_sink) in &query {
-        match *state {
-            // Start the fireplace sound effect if it isn't already running.
-            State::Running => {
-                audio_sink.play();
-            }
+// Kindle or burn out the fire, with the brightness and crackle following how strongly it burns.
+fn handle_intensity(
+    time: Res<Time>,
+    sprite_assets: Res<SpriteAssets>,
+    mut query: Query<(&State, &mut Intensity, &mut Sprite, &mut Fade), With<Fireplace>>,
+) {
+    for (state, mut intensity, mut sprite, mut fade) in &mut query {
+        // Nothing to do for a fire that's out.
+        if *state == State::Off && intensity.0 == 0.0 {
+            continue;
+        }
 
-            // Remove any existing sound effects.
-            State::Off => {
-                audio_sink.pause();
-            }
+        let previous = intensity.0;
+        let level = match *state {
+            State::Running => (previous + time.delta_secs() / KINDLE_TIME).min(1.0),
+            State::Off => (previous - time.delta_secs() / BURN_OUT_TIME).max(0.0),
+        };
+
+        // Only touch the fire when it's actually changed, so a roaring fire doesn't look changed every frame.
+        if !intensity.set_if_neq(Intensity(level)) {
+            continue;
+        }
+
+        if level == 0.0 {
+            sprite.image = sprite_assets.off_sprite.clone();
+            sprite.texture_atlas = None;
+            sprite.color = Color::WHITE;
+            fade.fade_out();
+            continue;
+        }
+
+        let brightness = DIM_BRIGHTNESS + (1.0 - DIM_BRIGHTNESS) * level;
+        sprite.color = Color::srgb(brightness, brightness, brightness);
+        if previous == 0.0 {
+            fade.fade_in();
+        }
+    }
+}
+
+// Let off embers as steadily as the fire burns.
+fn handle_embers(
+    fireplaces: Query<&Intensity, (With<Fireplace>, Changed<Intensity>)>,
+    mut embers: Query<(&ChildOf, &mut ParticleEmitter), With<Embers>>,
+) {
+    for (child_of, mut emitter) in &mut embers {
+        if let Ok(intensity) = fireplaces.get(child_of.parent()) {
+            emitter.rate = EMBER_RATE * intensity.0;
         }
     }
 }
@@ -164,6 +265,7 @@ fn init(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut fire_sounds: ResMut<Assets<FireSound>>,
 ) {
     // Load the running sprite sheet.
     let sprite = SpriteAssets {
@@ -184,16 +286,69 @@ fn init(
         Fireplace,
         AnimationConfig::new(0, 4, 6),
         State::Off,
-        AudioPlayer::new(asset_server.load("fireplace/fire.ogg")),
-        PlaybackSettings::LOOP
+        AudioPlayer(fire_sounds.add(FireSound::default())),
+        PlaybackSettings::ONCE
             .with_spatial(true)
             .with_volume(Volume::Linear(RUNNING_VOLUME))
             .paused(),
+        Bus::Ambience,
+        Fade::new(FadeCurve::Linear, FIRE_FADE, FIRE_FADE),
+        Attenuation::new(FIRE_MIN_DISTANCE, FIRE_MAX_DISTANCE, FIRE_ROLLOFF),
+        Caption("fire crackling".to_string()),
+        Intensity(0.0),
         Interactable {
             id: INTERACTABLE_ID.to_string(),
             height: SPRITE_HEIGHT * SPRITE_SCALE,
             width: SPRITE_WIDTH * SPRITE_SCALE,
             first: true,
+            stand_offset: STAND_OFFSET,
+            facing: Facing::Right,
+        },
+        Obstacle {
+            width: SPRITE_WIDTH * SPRITE_SCALE,
+            depth: OBSTACLE_DEPTH,
         },
+        children![
+            (
+                Embers,
+                Transform::from_translation(LOGS_OFFSET),
+                // Nothing until the fire catches.
+                ParticleEmitter {
+                    rate: 0.0,
+                    lifetime: 1.2..=2.4,
+                    shape: SpawnShape::Rect(Vec2::new(12.0, 2.0)),
+                    velocity_x: -4.0..=4.0,
+                    velocity_y: 12.0..=24.0,
+                    acceleration: Vec2::new(0.0, -4.0),
+                    drift: -6.0..=6.0,
+                    start_color: Color::srgb(1.0, 0.6, 0.2),
+                    end_color: Color::srgba(0.7, 0.15, 0.05, 0.0),
+                    opacity: 0.7..=1.0,
+                    flicker: 0.5,
+                    start_size: 1.5,
+                    end_size: 0.75,
+                    ..default()
+                },
+            ),
+            (
+                Sparks,
+                Transform::from_translation(LOGS_OFFSET),
+                // Only lets off bursts.
+                ParticleEmitter {
+                    rate: 0.0,
+                    lifetime: 0.3..=0.8,
+                    shape: SpawnShape::Rect(Vec2::new(8.0, 2.0)),
+                    velocity_x: -40.0..=40.0,
+                    velocity_y: 30.0..=80.0,
+                    acceleration: Vec2::new(0.0, -150.0),
+                    start_color: Color::srgb(1.0, 0.95, 0.6),
+                    end_color: Color::srgba(1.0, 0.45, 0.1, 0.0),
+                    flicker: 0.3,
+                    start_size: 1.0,
+                    end_size: 0.5,
+                    ..default()
+                },
+            ),
+        ],
     ));
 }
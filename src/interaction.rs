@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use crate::navigation::NavGraph;
+
 // Added to Interactable entities when they should be highlighted.
 #[derive(Component)]
 pub struct Highlight {
@@ -13,6 +15,13 @@ pub struct Interactor {
     pub height: f32,
 }
 
+// Which way an Interactor should face when using an Interactable.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Facing {
+    Left,
+    Right,
+}
+
 // Add to entities that can be interacted with.
 #[derive(Component)]
 pub struct Interactable {
@@ -20,18 +29,38 @@ pub struct Interactable {
     pub height: f32,
     pub width: f32,
     pub first: bool,
+    // Horizontal offset from the Interactable where an Interactor stands to use it.
+    pub stand_offset: f32,
+    pub facing: Facing,
 }
 
+// Add to Interactables that are always close by, like a pet following the man, so they're only in range
+// when nothing else is.
+#[derive(Component)]
+pub struct Underfoot;
+
 // Added to Interactor entities when they're in range of an Interactable.
 #[derive(Component)]
 pub struct InRange {
     pub id: String,
 }
 
+// What an Interactor is doing with an Interactable.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Verb {
+    Use,
+    Examine,
+    // Move on to the next of something, like a track on the stereo.
+    Skip,
+    // Stir something up, like the logs in the fireplace.
+    Poke,
+}
+
 // Message sent when an interaction is triggered.
 #[derive(Message)]
 pub struct InteractionEvent {
     pub id: String,
+    pub verb: Verb,
 }
 
 // Add the interaction systems.
@@ -40,6 +69,11 @@ pub fn add_systems(app: &mut App) {
         .add_systems(Update, detect_overlaps);
 }
 
+// Where to stand to use an interactable, keeping to the row the interactor is walking along.
+pub(crate) fn stand_position(graph: &NavGraph, transform: &Transform, interactable: &Interactable, y: f32) -> Vec2 {
+    graph.clamp(Vec2::new(transform.translation.x + interactable.stand_offset, y))
+}
+
 // Simple AABB (Axis-Aligned Bounding Box) overlap detection.
 fn aabb_overlap(pos_1: Vec2, width_1: f32, height_1: f32, pos_2: Vec2, width_2: f32, height_2: f32) -> bool {
     let half_width_1 = width_1 / 2.0;
@@ -65,15 +99,22 @@ fn detect_overlaps(
     mut commands: Commands,
     time: Res<Time>,
     interactors: Query<(Entity, &Transform, &Interactor)>,
-    interactables: Query<(Entity, &Transform, &Interactable)>,
+    interactables: Query<(Entity, &Transform, &Interactable, Has<Underfoot>)>,
     in_range: Query<(Entity, &InRange)>,
 ) {
     for (interactor_entity, interactor_transform, interactor) in &interactors {
         let mut found_overlap = None;
         let mut interactable_entity = None;
+        let mut nearest = (true, f32::INFINITY);
+
+        // Check against all interactables, preferring the nearest when several overlap, and anything over
+        // what's underfoot.
+        for (entity, interactable_transform, interactable, underfoot) in &interactables {
+            let distance = interactor_transform
+                .translation
+                .truncate()
+                .distance(interactable_transform.translation.truncate());
 
-        // Check against all interactables.
-        for (entity, interactable_transform, interactable) in &interactables {
             if aabb_overlap(
                 interactor_transform.translation.truncate(),
                 interactor.width,
@@ -81,9 +122,14 @@ fn detect_overlaps(
                 interactable_transform.translation.truncate(),
                 interactable.width,
                 interactable.height,
-            ) {
+            ) && (underfoot, distance) < nearest
+            {
+                if let Some(previous) = interactable_entity {
+                    commands.entity(previous).remove::<Highlight>();
+                }
                 found_overlap = Some(interactable.id.clone());
                 interactable_entity = Some(entity);
+                nearest = (underfoot, distance);
             } else {
                 commands.entity(entity).remove::<Highlight>();
             }
@@ -0,0 +1,236 @@
+use bevy::{asset::LoadContext, audio::AudioSinkPlayback, prelude::*};
+
+use crate::{
+    dialogue::{self, DialogueState},
+    text_asset::{TextAsset, TextAssetError, TextAssetLoader},
+};
+
+// Timed lyrics for a track loaded from an `.lrc` file.
+//
+// Each line starts with one or more `[mm:ss.xx]` times it's sung at, followed by its text. A line with
+// no text clears the lyrics until the next one. `[offset: ms]` shows every line that much earlier, and
+// other tags like `[ti: title]` are ignored.
+#[derive(Asset, TypePath)]
+pub struct Lyrics {
+    // Sorted by time.
+    lines: Vec<LyricLine>,
+}
+
+struct LyricLine {
+    // Seconds into the track.
+    time: f32,
+    text: String,
+}
+
+// Add to a streamed audio player to show its lyrics as it plays.
+#[derive(Component)]
+pub struct Sung(pub Handle<Lyrics>);
+
+// The line being sung, and the one coming up after it.
+#[derive(Component)]
+struct CurrentLine;
+
+#[derive(Component)]
+struct NextLine;
+
+#[derive(Component)]
+struct LyricsOverlay;
+
+const CURRENT_SIZE: f32 = 20.0;
+const NEXT_SIZE: f32 = 14.0;
+const CURRENT_COLOR: Color = Color::srgb(1.0, 0.85, 0.4);
+const NEXT_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.6);
+
+// Add the lyrics systems.
+pub fn add_systems(app: &mut App) {
+    app.init_asset::<Lyrics>()
+        .init_asset_loader::<TextAssetLoader<Lyrics>>()
+        .add_systems(Startup, init)
+        .add_systems(Update, handle_lyrics);
+}
+
+impl TextAsset for Lyrics {
+    const EXTENSIONS: &'static [&'static str] = &["lrc"];
+
+    fn from_text(source: &str, _load_context: &mut LoadContext<'_>) -> Result<Self, TextAssetError> {
+        Lyrics::parse(source)
+    }
+}
+
+impl Lyrics {
+    fn parse(source: &str) -> Result<Self, TextAssetError> {
+        let mut lines = Vec::new();
+        let mut offset = 0.0;
+
+        for (index, line) in source.lines().enumerate() {
+            let mut line = line.trim();
+            let error = |message: &str| TextAssetError::at(index + 1, message);
+
+            if line.is_empty() {
+                continue;
+            }
+
+            // Gather the tags at the start of the line, leaving its text.
+            let mut times = Vec::new();
+            while let Some(rest) = line.strip_prefix('[') {
+                let (tag, text) = rest.split_once(']').ok_or_else(|| error("unclosed tag"))?;
+                line = text.trim_start();
+
+                let (key, value) = tag
+                    .split_once(':')
+                    .ok_or_else(|| error("expected 'mm:ss' or 'key: value'"))?;
+                if let Ok(minutes) = key.trim().parse::<u32>() {
+                    let seconds: f32 = value.trim().parse().map_err(|_| error("invalid time"))?;
+                    times.push(minutes as f32 * 60.0 + seconds);
+                } else if key.trim() == "offset" {
+                    let milliseconds: f32 = value.trim().parse().map_err(|_| error("offset must be milliseconds"))?;
+                    offset = milliseconds / 1000.0;
+                }
+            }
+
+            if times.is_empty() && !line.is_empty() {
+                return Err(error("lyrics need a [mm:ss] time"));
+            }
+            lines.extend(times.into_iter().map(|time| LyricLine {
+                time,
+                text: line.to_string(),
+            }));
+        }
+
+        // The offset applies wherever it appears in the file.
+        for line in &mut lines {
+            line.time = (line.time - offset).max(0.0);
+        }
+        lines.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(Lyrics { lines })
+    }
+
+    // The index of the line being sung at a position in the track, if it has started.
+    fn line_at(&self, position: f32) -> Option<usize> {
+        self.lines.partition_point(|line| line.time <= position).checked_sub(1)
+    }
+}
+
+// Show the line being sung along with the next one, holding still while the music is paused.
+fn handle_lyrics(
+    dialogue: Res<DialogueState>,
+    lyrics: Res<Assets<Lyrics>>,
+    players: Query<(&Sung, &SpatialAudioSink)>,
+    mut overlay: Single<&mut Visibility, With<LyricsOverlay>>,
+    mut current: Single<&mut Text, (With<CurrentLine>, Without<NextLine>)>,
+    mut next: Single<&mut Text, (With<NextLine>, Without<CurrentLine>)>,
+) {
+    // The playing position is taken from the sink, so it stays in step through pauses.
+    let singing = players
+        .iter()
+        .find(|(_, sink)| !sink.is_paused())
+        .and_then(|(sung, sink)| Some((lyrics.get(&sung.0)?, sink.position().as_secs_f32())));
+
+    let Some((lyrics, position)) = singing.filter(|_| !dialogue::is_open(dialogue)) else {
+        overlay.set_if_neq(Visibility::Hidden);
+        return;
+    };
+
+    let index = lyrics.line_at(position);
+    let text = |index: usize| lyrics.lines.get(index).map_or("", |line| line.text.as_str());
+    let current_text = index.map_or("", text);
+    let next_text = text(index.map_or(0, |index| index + 1));
+    if current.0 != current_text {
+        current.0 = current_text.to_string();
+    }
+    if next.0 != next_text {
+        next.0 = next_text.to_string();
+    }
+    overlay.set_if_neq(Visibility::Inherited);
+}
+
+// Create the lyrics overlay along the bottom of the screen, hidden until something is sung.
+fn init(mut commands: Commands) {
+    commands.spawn((
+        LyricsOverlay,
+        Node {
+            position_type: PositionType::Absolute,
+            left: px(0),
+            right: px(0),
+            bottom: px(24),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: px(4),
+            ..default()
+        },
+        Visibility::Hidden,
+        children![
+            (
+                CurrentLine,
+                Text::default(),
+                TextFont::from_font_size(CURRENT_SIZE),
+                TextColor(CURRENT_COLOR),
+            ),
+            (
+                NextLine,
+                Text::default(),
+                TextFont::from_font_size(NEXT_SIZE),
+                TextColor(NEXT_COLOR)
+            ),
+        ],
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(lyrics: &Lyrics) -> Vec<(f32, &str)> {
+        lyrics
+            .lines
+            .iter()
+            .map(|line| (line.time, line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn lines_with_several_times_are_sung_at_each() {
+        let lyrics = Lyrics::parse("[ti: Song]\n[00:01.00][00:05.50]Chorus\n[00:03.00]Verse\n[00:07.00]\n").unwrap();
+        assert_eq!(
+            times(&lyrics),
+            [(1.0, "Chorus"), (3.0, "Verse"), (5.5, "Chorus"), (7.0, "")]
+        );
+    }
+
+    #[test]
+    fn offset_moves_every_line() {
+        let earlier = Lyrics::parse("[00:02.00]One\n[offset: 500]\n[00:04.00]Two\n").unwrap();
+        assert_eq!(times(&earlier), [(1.5, "One"), (3.5, "Two")]);
+
+        let later = Lyrics::parse("[offset: -1000]\n[00:00.00]One\n[00:04.00]Two\n").unwrap();
+        assert_eq!(times(&later), [(1.0, "One"), (5.0, "Two")]);
+
+        let clamped = Lyrics::parse("[offset: 3000]\n[00:01.00]One\n").unwrap();
+        assert_eq!(times(&clamped), [(0.0, "One")]);
+    }
+
+    #[test]
+    fn finds_the_line_being_sung() {
+        let lyrics = Lyrics::parse("[00:02.00]One\n[00:04.00]Two\n").unwrap();
+        assert_eq!(lyrics.line_at(0.0), None);
+        assert_eq!(lyrics.line_at(1.99), None);
+        assert_eq!(lyrics.line_at(2.0), Some(0));
+        assert_eq!(lyrics.line_at(3.0), Some(0));
+        assert_eq!(lyrics.line_at(60.0), Some(1));
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        for (source, line) in [
+            ("[00:01.00]One\nTwo\n", 2),
+            ("\n[00:01.00\n", 2),
+            ("[00:xx]One\n", 1),
+            ("[offset: soon]\n", 1),
+        ] {
+            match Lyrics::parse(source) {
+                Err(TextAssetError::Parse { line: error_line, .. }) => assert_eq!(error_line, line, "{source:?}"),
+                _ => panic!("expected {source:?} to fail on line {line}"),
+            }
+        }
+    }
+}
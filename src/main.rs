@@ -1,13 +1,33 @@
 //! Animate a sprite in response to a keyboard event.
 
+mod acoustics;
+mod analysis;
 mod animation;
 mod app;
 mod background;
+mod bubble;
+mod captions;
+mod crackle;
+mod dialogue;
 mod fireplace;
 mod house;
 mod interaction;
+mod lyrics;
+mod mixer;
+mod navigation;
+mod notice;
+mod npc;
+mod particles;
+mod pet;
+mod playlist;
+mod settings;
 mod snow;
+mod soundbank;
 mod stereo;
+mod stream;
+mod surface;
+mod synth;
+mod text_asset;
 mod theman;
 mod tree;
 
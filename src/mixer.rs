@@ -0,0 +1,378 @@
+use bevy::{
+    audio::{AudioSinkPlayback, Volume},
+    prelude::*,
+};
+
+use crate::{
+    dialogue::{self, DialogueState},
+    notice::Notice,
+};
+
+// Add to every entity with an `AudioPlayer` to mix it with the rest of its kind.
+#[derive(Clone, Component, Copy, Debug, PartialEq)]
+pub enum Bus {
+    Music,
+    Ambience,
+    Sfx,
+}
+
+// Add to audio players that should lower the music while they play.
+#[derive(Component)]
+pub struct Ducking;
+
+// The shape of a fade between silence and full volume.
+#[derive(Clone, Copy)]
+pub enum FadeCurve {
+    Linear,
+    // Swells in slowly and drops away quickly, closer to how loudness is heard.
+    EaseIn,
+}
+
+// Add to a looping player to fade it in and out instead of cutting it, pausing it once silent.
+#[derive(Component)]
+pub struct Fade {
+    pub curve: FadeCurve,
+    // Seconds to go from silent to full volume, and back.
+    pub fade_in: f32,
+    pub fade_out: f32,
+    // How far through the fade the player is, from 0 silent to 1 full, and where it's heading.
+    progress: f32,
+    target: f32,
+}
+
+// Add to a spatial player to quieten it with its distance from the listener.
+//
+// Within `min_distance` it plays at full volume. Further away it drops off, more steeply the higher the
+// `rolloff`, until it stops getting any quieter at `max_distance`.
+#[derive(Component)]
+pub struct Attenuation {
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub rolloff: f32,
+    // How much the distance scales the volume of the player right now.
+    gain: f32,
+}
+
+// The volume a player was spawned with, before its bus is applied.
+#[derive(Component)]
+struct BaseVolume(Volume);
+
+#[derive(Clone, Copy)]
+pub struct BusSettings {
+    pub volume: f32,
+    pub muted: bool,
+}
+
+// Bus volumes and mutes, applied to every player on the bus including ones already playing.
+#[derive(Resource)]
+pub struct Mixer {
+    pub music: BusSettings,
+    pub ambience: BusSettings,
+    pub sfx: BusSettings,
+    // How far the music is currently ducked, from 1 at full volume down to DUCK_VOLUME.
+    duck: f32,
+    // The bus the volume keys adjust.
+    selected: Bus,
+}
+
+const VOLUME_STEP: f32 = 0.1;
+
+const DUCK_VOLUME: f32 = 0.3;
+// How quickly the music fades down when ducked, and back up afterwards, per second.
+const DUCK_ATTACK: f32 = 3.0;
+const DUCK_RELEASE: f32 = 0.8;
+
+// Add the mixer systems.
+pub fn add_systems(app: &mut App) {
+    app.init_resource::<Mixer>()
+        .add_systems(Update, (handle_keys, handle_ducking, handle_fades, handle_attenuation))
+        // Before the audio systems start any new sinks.
+        .add_systems(PostUpdate, handle_volume.before(TransformSystems::Propagate));
+}
+
+impl Default for BusSettings {
+    fn default() -> Self {
+        BusSettings {
+            volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Mixer {
+            music: BusSettings::default(),
+            ambience: BusSettings::default(),
+            sfx: BusSettings::default(),
+            duck: 1.0,
+            selected: Bus::Music,
+        }
+    }
+}
+
+impl Bus {
+    pub fn name(self) -> &'static str {
+        match self {
+            Bus::Music => "Music",
+            Bus::Ambience => "Ambience",
+            Bus::Sfx => "Sound effects",
+        }
+    }
+}
+
+impl Mixer {
+    pub fn bus(&self, bus: Bus) -> &BusSettings {
+        match bus {
+            Bus::Music => &self.music,
+            Bus::Ambience => &self.ambience,
+            Bus::Sfx => &self.sfx,
+        }
+    }
+
+    pub fn bus_mut(&mut self, bus: Bus) -> &mut BusSettings {
+        match bus {
+            Bus::Music => &mut self.music,
+            Bus::Ambience => &mut self.ambience,
+            Bus::Sfx => &mut self.sfx,
+        }
+    }
+
+    // How much a bus scales the volume of its players.
+    pub fn gain(&self, bus: Bus) -> f32 {
+        let settings = self.bus(bus);
+        let duck = if bus == Bus::Music { self.duck } else { 1.0 };
+        if settings.muted { 0.0 } else { settings.volume * duck }
+    }
+}
+
+impl FadeCurve {
+    fn apply(self, progress: f32) -> f32 {
+        match self {
+            FadeCurve::Linear => progress,
+            FadeCurve::EaseIn => progress * progress,
+        }
+    }
+}
+
+impl Fade {
+    // A fade starting silent.
+    pub fn new(curve: FadeCurve, fade_in: f32, fade_out: f32) -> Self {
+        Fade {
+            curve,
+            fade_in,
+            fade_out,
+            progress: 0.0,
+            target: 0.0,
+        }
+    }
+
+    pub fn fade_in(&mut self) {
+        self.fade_to(1.0);
+    }
+
+    pub fn fade_out(&mut self) {
+        self.fade_to(0.0);
+    }
+
+    // Head towards a level part way between silent and full.
+    pub fn fade_to(&mut self, level: f32) {
+        self.target = level.clamp(0.0, 1.0);
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.progress == 0.0
+    }
+
+    // How much the fade scales the volume of its player.
+    pub fn gain(&self) -> f32 {
+        self.curve.apply(self.progress)
+    }
+}
+
+impl Attenuation {
+    pub fn new(min_distance: f32, max_distance: f32, rolloff: f32) -> Self {
+        Attenuation {
+            min_distance,
+            max_distance,
+            rolloff,
+            gain: 1.0,
+        }
+    }
+
+    // How much the volume is scaled at a distance from the listener.
+    fn gain_at(&self, distance: f32) -> f32 {
+        let distance = distance.clamp(self.min_distance, self.max_distance.max(self.min_distance));
+        self.min_distance / (self.min_distance + self.rolloff * (distance - self.min_distance)).max(f32::EPSILON)
+    }
+}
+
+// 1, 2 and 3 pick the music, ambience or sound effects bus, - and = turn it down or up, M mutes it. The bus
+// and its level are shown on screen with every change.
+fn handle_keys(keyboard: Res<ButtonInput<KeyCode>>, mut mixer: ResMut<Mixer>, mut notices: MessageWriter<Notice>) {
+    let adjust = [KeyCode::Minus, KeyCode::Equal, KeyCode::KeyM];
+    let select = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3];
+    // Only touch the mixer on a key press, so its volumes aren't reapplied every frame.
+    if !keyboard.any_just_pressed(adjust) && !keyboard.any_just_pressed(select) {
+        return;
+    }
+
+    for (key, bus) in select.into_iter().zip([Bus::Music, Bus::Ambience, Bus::Sfx]) {
+        if keyboard.just_pressed(key) {
+            mixer.selected = bus;
+        }
+    }
+
+    let selected = mixer.selected;
+    if keyboard.just_pressed(KeyCode::Minus) {
+        let settings = mixer.bus_mut(selected);
+        settings.volume = (settings.volume - VOLUME_STEP).max(0.0);
+    } else if keyboard.just_pressed(KeyCode::Equal) {
+        let settings = mixer.bus_mut(selected);
+        settings.volume = (settings.volume + VOLUME_STEP).min(1.0);
+    } else if keyboard.just_pressed(KeyCode::KeyM) {
+        let settings = mixer.bus_mut(selected);
+        settings.muted = !settings.muted;
+    }
+
+    let settings = *mixer.bus(selected);
+    notices.write(Notice(if settings.muted {
+        format!("{} muted", selected.name())
+    } else {
+        format!("{} volume {:.0}%", selected.name(), settings.volume * 100.0)
+    }));
+}
+
+// Lower the music while a conversation is open or a ducking sound plays, and bring it back after.
+fn handle_ducking(
+    time: Res<Time>,
+    dialogue: Res<DialogueState>,
+    mut mixer: ResMut<Mixer>,
+    ducking: Query<(), With<Ducking>>,
+) {
+    let (target, rate) = if dialogue::is_open(dialogue) || !ducking.is_empty() {
+        (DUCK_VOLUME, DUCK_ATTACK)
+    } else {
+        (1.0, DUCK_RELEASE)
+    };
+
+    // Only touch the mixer while moving, so players aren't updated every frame.
+    if mixer.duck != target {
+        let step = rate * time.delta_secs();
+        mixer.duck = if mixer.duck < target {
+            (mixer.duck + step).min(target)
+        } else {
+            (mixer.duck - step).max(target)
+        };
+    }
+}
+
+// Move fades along, starting players as they fade in and pausing them once faded out.
+fn handle_fades(time: Res<Time>, mut query: Query<(&mut Fade, Option<&AudioSink>, Option<&SpatialAudioSink>)>) {
+    for (mut fade, sink, spatial_sink) in &mut query {
+        if fade.progress != fade.target {
+            let (duration, direction) = if fade.target > fade.progress {
+                (fade.fade_in, 1.0)
+            } else {
+                (fade.fade_out, -1.0)
+            };
+            let step = if duration > 0.0 {
+                time.delta_secs() / duration
+            } else {
+                1.0
+            };
+            let progress = fade.progress + step * direction;
+            fade.progress = if direction > 0.0 {
+                progress.min(fade.target)
+            } else {
+                progress.max(fade.target)
+            };
+        }
+
+        let silent = fade.progress == 0.0 && fade.target == 0.0;
+        if let Some(sink) = sink {
+            toggle(sink, silent);
+        }
+        if let Some(sink) = spatial_sink {
+            toggle(sink, silent);
+        }
+    }
+}
+
+// Quieten players with their distance from the listener, wherever it is.
+fn handle_attenuation(
+    listener: Single<&GlobalTransform, With<SpatialListener>>,
+    mut players: Query<(&GlobalTransform, &mut Attenuation)>,
+) {
+    for (transform, mut attenuation) in &mut players {
+        let distance = transform
+            .translation()
+            .truncate()
+            .distance(listener.translation().truncate());
+        let gain = attenuation.gain_at(distance);
+
+        // Only touch players that have moved, so they aren't updated every frame.
+        if (attenuation.gain - gain).abs() > f32::EPSILON {
+            attenuation.gain = gain;
+        }
+    }
+}
+
+// Pause a sink once it's silent, and start it again once it isn't.
+fn toggle(sink: &impl AudioSinkPlayback, silent: bool) {
+    if silent && !sink.is_paused() {
+        sink.pause();
+    } else if !silent && sink.is_paused() {
+        sink.play();
+    }
+}
+
+// The volume for a player on a bus, fading and quietened by distance if it has those.
+fn volume(mixer: &Mixer, bus: Bus, base: Volume, fade: Option<&Fade>, attenuation: Option<&Attenuation>) -> Volume {
+    let gain = mixer.gain(bus) * fade.map_or(1.0, Fade::gain) * attenuation.map_or(1.0, |attenuation| attenuation.gain);
+    base * Volume::Linear(gain)
+}
+
+// Set the volume of new players from their bus, and update everything playing when the mixer, a fade or a
+// distance changes.
+fn handle_volume(
+    mut commands: Commands,
+    mixer: Res<Mixer>,
+    global_volume: Res<GlobalVolume>,
+    mut new_players: Query<
+        (Entity, &Bus, &mut PlaybackSettings, Option<&Fade>, Option<&Attenuation>),
+        Without<BaseVolume>,
+    >,
+    mut players: Query<(
+        &Bus,
+        &BaseVolume,
+        &mut PlaybackSettings,
+        Option<Ref<Fade>>,
+        Option<Ref<Attenuation>>,
+        Option<&mut AudioSink>,
+        Option<&mut SpatialAudioSink>,
+    )>,
+) {
+    // Players that haven't started yet pick up the bus volume when their sink is created.
+    for (entity, bus, mut settings, fade, attenuation) in &mut new_players {
+        commands.entity(entity).insert(BaseVolume(settings.volume));
+        settings.volume = volume(&mixer, *bus, settings.volume, fade, attenuation);
+    }
+
+    for (bus, base, mut settings, fade, attenuation, sink, spatial_sink) in &mut players {
+        if !mixer.is_changed()
+            && !fade.as_ref().is_some_and(Ref::is_changed)
+            && !attenuation.as_ref().is_some_and(Ref::is_changed)
+        {
+            continue;
+        }
+
+        settings.volume = volume(&mixer, *bus, base.0, fade.as_deref(), attenuation.as_deref());
+        if let Some(mut sink) = sink {
+            sink.set_volume(settings.volume * global_volume.volume);
+        }
+        if let Some(mut sink) = spatial_sink {
+            sink.set_volume(settings.volume * global_volume.volume);
+        }
+    }
+}
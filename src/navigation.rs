@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+
+// Add to entities that characters should walk around.
+#[derive(Component)]
+pub struct Obstacle {
+    pub width: f32,
+    pub depth: f32,
+}
+
+// Grid of walkable floor positions used to route characters around obstacles.
+#[derive(Default, Resource)]
+pub struct NavGraph {
+    columns: usize,
+    rows: usize,
+    walkable: Vec<bool>,
+    obstacles: Vec<Rect>,
+}
+
+// Open set entry for the A* search, ordered so the cheapest node is popped first.
+#[derive(PartialEq)]
+struct Candidate {
+    cost: f32,
+    node: usize,
+}
+
+// The floor area characters can stand on.
+pub const FLOOR_MIN: Vec2 = Vec2::new(-200.0, -86.0);
+pub const FLOOR_MAX: Vec2 = Vec2::new(200.0, -68.0);
+
+const NODE_SPACING: f32 = 4.0;
+const AGENT_RADIUS: f32 = 4.0;
+
+// Add the navigation systems.
+pub fn add_systems(app: &mut App) {
+    app.init_resource::<NavGraph>().add_systems(PreUpdate, handle_obstacles);
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl NavGraph {
+    // Rebuild the walkable grid from the obstacle footprints.
+    fn rebuild(&mut self, obstacles: Vec<Rect>) {
+        let size = FLOOR_MAX - FLOOR_MIN;
+        self.columns = (size.x / NODE_SPACING) as usize + 1;
+        self.rows = (size.y / NODE_SPACING) as usize + 1;
+        self.obstacles = obstacles;
+        self.walkable = (0..self.columns * self.rows)
+            .map(|node| !self.blocked(self.position(node)))
+            .collect();
+    }
+
+    // Floor position of a graph node.
+    fn position(&self, node: usize) -> Vec2 {
+        let column = node % self.columns;
+        let row = node / self.columns;
+        FLOOR_MIN + Vec2::new(column as f32, row as f32) * NODE_SPACING
+    }
+
+    // Check if a position is inside any obstacle footprint.
+    fn blocked(&self, position: Vec2) -> bool {
+        self.obstacles.iter().any(|obstacle| obstacle.contains(position))
+    }
+
+    // Check if a straight walk between two positions stays clear of obstacles.
+    fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        let steps = (from.distance(to) / (NODE_SPACING / 2.0)).ceil().max(1.0) as usize;
+        (0..=steps).all(|step| !self.blocked(from.lerp(to, step as f32 / steps as f32)))
+    }
+
+    // Find the walkable node closest to a position.
+    fn nearest_node(&self, position: Vec2) -> Option<usize> {
+        (0..self.walkable.len())
+            .filter(|node| self.walkable[*node])
+            .min_by(|a, b| {
+                self.position(*a)
+                    .distance_squared(position)
+                    .total_cmp(&self.position(*b).distance_squared(position))
+            })
+    }
+
+    // Walkable nodes adjacent to a node, including diagonals.
+    fn neighbors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        let column = (node % self.columns) as isize;
+        let row = (node / self.columns) as isize;
+
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (column + dx, row + dy)))
+            .filter(move |(x, y)| {
+                (*x, *y) != (column, row)
+                    && *x >= 0
+                    && *y >= 0
+                    && (*x as usize) < self.columns
+                    && (*y as usize) < self.rows
+            })
+            .map(|(x, y)| y as usize * self.columns + x as usize)
+            .filter(|neighbor| self.walkable[*neighbor])
+    }
+
+    // Keep a position on the floor.
+    pub fn clamp(&self, position: Vec2) -> Vec2 {
+        position.clamp(FLOOR_MIN, FLOOR_MAX)
+    }
+
+    // Find a list of waypoints from one floor position to another, routing around obstacles.
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<VecDeque<Vec2>> {
+        let from = self.clamp(from);
+        let mut to = self.clamp(to);
+
+        if self.line_of_sight(from, to) {
+            return Some(VecDeque::from([to]));
+        }
+
+        let start = self.nearest_node(from)?;
+        let goal = self.nearest_node(to)?;
+
+        // A destination inside an obstacle walks to the closest free spot instead.
+        if self.blocked(to) {
+            to = self.position(goal);
+        }
+
+        // A* search over the grid.
+        let mut open = BinaryHeap::from([Candidate { cost: 0.0, node: start }]);
+        let mut came_from = vec![None; self.walkable.len()];
+        let mut best = vec![f32::INFINITY; self.walkable.len()];
+        best[start] = 0.0;
+
+        while let Some(Candidate { node, .. }) = open.pop() {
+            if node == goal {
+                break;
+            }
+
+            for neighbor in self.neighbors(node) {
+                let cost = best[node] + self.position(node).distance(self.position(neighbor));
+                if cost < best[neighbor] {
+                    best[neighbor] = cost;
+                    came_from[neighbor] = Some(node);
+                    open.push(Candidate {
+                        cost: cost + self.position(neighbor).distance(to),
+                        node: neighbor,
+                    });
+                }
+            }
+        }
+
+        if goal != start && came_from[goal].is_none() {
+            return None;
+        }
+
+        // Walk back from the goal to build the route.
+        let mut nodes = vec![to];
+        let mut current = goal;
+        while let Some(previous) = came_from[current] {
+            nodes.push(self.position(current));
+            current = previous;
+        }
+        nodes.push(from);
+        nodes.reverse();
+
+        // Skip any waypoints that can be walked past in a straight line.
+        let mut waypoints = VecDeque::new();
+        let mut anchor = 0;
+        while anchor < nodes.len() - 1 {
+            let next = (anchor + 1..nodes.len())
+                .rev()
+                .find(|index| self.line_of_sight(nodes[anchor], nodes[*index]))
+                .unwrap_or(anchor + 1);
+            waypoints.push_back(nodes[next]);
+            anchor = next;
+        }
+
+        Some(waypoints)
+    }
+}
+
+// Rebuild the navigation graph when obstacles are added or removed.
+fn handle_obstacles(
+    mut graph: ResMut<NavGraph>,
+    changed: Query<(), Changed<Obstacle>>,
+    mut removed: RemovedComponents<Obstacle>,
+    obstacles: Query<(&Transform, &Obstacle)>,
+) {
+    if changed.is_empty() && removed.read().count() == 0 && !graph.walkable.is_empty() {
+        return;
+    }
+
+    // Obstacles stand against the back of the floor, padded so characters don't clip them.
+    let footprints = obstacles
+        .iter()
+        .map(|(transform, obstacle)| {
+            Rect::new(
+                transform.translation.x - obstacle.width / 2.0,
+                FLOOR_MAX.y - obstacle.depth,
+                transform.translation.x + obstacle.width / 2.0,
+                FLOOR_MAX.y,
+            )
+            .inflate(AGENT_RADIUS)
+        })
+        .collect();
+    graph.rebuild(footprints);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A graph over the whole floor with the given obstacle footprints.
+    fn graph(obstacles: Vec<Rect>) -> NavGraph {
+        let mut graph = NavGraph::default();
+        graph.rebuild(obstacles);
+        graph
+    }
+
+    #[test]
+    fn path_goes_around_obstacle() {
+        let obstacle = Rect::new(-20.0, -80.0, 20.0, FLOOR_MAX.y);
+        let graph = graph(vec![obstacle]);
+        let from = Vec2::new(-50.0, -72.0);
+        let to = Vec2::new(50.0, -72.0);
+
+        let route = graph.find_path(from, to).expect("a path around the obstacle");
+        assert_eq!(route.back(), Some(&to));
+        assert!(route.len() > 1);
+
+        // Every leg of the route stays clear of the obstacle.
+        let mut previous = from;
+        for waypoint in route {
+            assert!(graph.line_of_sight(previous, waypoint));
+            previous = waypoint;
+        }
+    }
+
+    #[test]
+    fn unreachable_target_has_no_path() {
+        // A wall from the front of the floor to the back.
+        let wall = Rect::new(-20.0, FLOOR_MIN.y - 10.0, 20.0, FLOOR_MAX.y + 10.0);
+        let graph = graph(vec![wall]);
+
+        assert!(
+            graph
+                .find_path(Vec2::new(-50.0, -75.0), Vec2::new(50.0, -75.0))
+                .is_none()
+        );
+    }
+}
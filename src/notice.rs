@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+
+// Message sent to show a short note on screen, like a volume change, replacing any note already up.
+#[derive(Message)]
+pub struct Notice(pub String);
+
+// The text showing the latest notice, and how long it has left.
+#[derive(Component)]
+struct NoticeText(Timer);
+
+const NOTICE_DURATION: f32 = 1.5;
+const NOTICE_SIZE: f32 = 14.0;
+
+// Add the notice systems.
+pub fn add_systems(app: &mut App) {
+    app.add_message::<Notice>()
+        .add_systems(Startup, init)
+        .add_systems(Update, handle_notices);
+}
+
+// Show the latest notice for a moment, then hide it.
+fn handle_notices(
+    time: Res<Time>,
+    mut events: MessageReader<Notice>,
+    notice: Single<(&mut Text, &mut NoticeText, &mut Visibility)>,
+) {
+    let (mut text, mut notice_text, mut visibility) = notice.into_inner();
+
+    if let Some(event) = events.read().last() {
+        text.0.clone_from(&event.0);
+        notice_text.0.reset();
+        visibility.set_if_neq(Visibility::Inherited);
+        return;
+    }
+
+    notice_text.0.tick(time.delta());
+    if notice_text.0.just_finished() {
+        visibility.set_if_neq(Visibility::Hidden);
+    }
+}
+
+// Create the notice text in the top right corner, hidden until there's something to say.
+fn init(mut commands: Commands) {
+    commands.spawn((
+        NoticeText(Timer::from_seconds(NOTICE_DURATION, TimerMode::Once)),
+        Text::default(),
+        TextFont::from_font_size(NOTICE_SIZE),
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(12),
+            right: px(12),
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
@@ -0,0 +1,365 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::collections::VecDeque;
+
+use crate::{
+    animation::{AnimationConfig, CharacterSprites, WALKING_LAST_FRAME},
+    dialogue,
+    interaction::{self, Facing, Interactable, InteractionEvent, Interactor, Verb},
+    navigation::{FLOOR_MAX, FLOOR_MIN, NavGraph, Obstacle},
+};
+
+#[derive(Clone, Component, Copy, PartialEq)]
+enum State {
+    Idle,
+    Walking,
+    Talking,
+}
+
+#[derive(Clone, Component, Copy, PartialEq)]
+enum Direction {
+    Left,
+    Right,
+}
+
+#[derive(Message)]
+struct Trigger {
+    entity: Entity,
+    state: State,
+    direction: Direction,
+}
+
+// Waypoints the character is walking through.
+#[derive(Component)]
+struct Route(VecDeque<Vec2>);
+
+// Added to characters walking to use a prop, sent as an interaction on arrival.
+#[derive(Component)]
+struct Errand {
+    id: String,
+    facing: Direction,
+}
+
+// Time left before the character picks somewhere new to go.
+#[derive(Component)]
+struct WanderTimer(Timer);
+
+#[derive(Component)]
+pub struct Npc;
+
+const SPRITE_SCALE: f32 = 1.5;
+const SPRITE_SIZE: f32 = 32.;
+
+const WALKING_SPEED: f32 = 22.0;
+const ARRIVE_DISTANCE: f32 = 1.0;
+
+const AVOID_RADIUS: f32 = 18.0;
+const AVOID_STRENGTH: f32 = 25.0;
+
+const WANDER_DELAY_MIN: f32 = 4.0;
+const WANDER_DELAY_MAX: f32 = 12.0;
+const ERRAND_CHANCE: f64 = 0.2;
+
+const TALK_DURATION: f32 = 4.0;
+const TALK_DISTANCE: f32 = 20.0;
+
+// Family members and a visitor, with their starting spot and tint.
+const CHARACTERS: [(&str, f32, Color); 3] = [
+    ("grandma", 40.0, Color::srgb(0.85, 0.8, 1.0)),
+    ("kid", -130.0, Color::srgb(1.0, 0.85, 0.7)),
+    ("visitor", 192.0, Color::srgb(0.7, 0.9, 0.8)),
+];
+
+// Add the npc systems.
+pub fn add_systems(app: &mut App) {
+    app.add_message::<Trigger>().add_systems(Startup, init).add_systems(
+        Update,
+        (
+            handle_animations,
+            handle_wander,
+            handle_movement,
+            handle_errands,
+            handle_interaction,
+            handle_talking.run_if(not(dialogue::is_open)),
+            trigger_animation,
+        ),
+    );
+}
+
+// Loop through all the characters' sprites and advance their walk cycle.
+fn handle_animations(time: Res<Time>, mut query: Query<(&mut AnimationConfig, &mut Sprite, &State), With<Npc>>) {
+    for (mut config, mut sprite, state) in &mut query {
+        // Only walking has more than one frame.
+        if *state != State::Walking {
+            continue;
+        }
+
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            config.advance(time.delta(), atlas, true);
+        }
+    }
+}
+
+// Pick somewhere to go when idle, occasionally heading off to use a prop. Only props that stay put count,
+// not the cat or anyone else wandering about.
+fn handle_wander(
+    mut commands: Commands,
+    time: Res<Time>,
+    graph: Res<NavGraph>,
+    mut query: Query<(Entity, &Transform, &State, &mut WanderTimer), With<Npc>>,
+    props: Query<(&Transform, &Interactable), With<Obstacle>>,
+) {
+    let mut rng = rand::rng();
+
+    for (entity, transform, state, mut timer) in &mut query {
+        if *state != State::Idle {
+            continue;
+        }
+
+        timer.0.tick(time.delta());
+        if !timer.0.just_finished() {
+            continue;
+        }
+        timer.0 = Timer::from_seconds(rng.random_range(WANDER_DELAY_MIN..=WANDER_DELAY_MAX), TimerMode::Once);
+
+        let position = transform.translation.truncate();
+        let props: Vec<_> = props.iter().collect();
+
+        if !props.is_empty() && rng.random_bool(ERRAND_CHANCE) {
+            let (prop_transform, prop) = props[rng.random_range(0..props.len())];
+            let stand = interaction::stand_position(&graph, prop_transform, prop, position.y);
+            if let Some(route) = graph.find_path(position, stand) {
+                commands.entity(entity).insert((
+                    Route(route),
+                    Errand {
+                        id: prop.id.clone(),
+                        facing: match prop.facing {
+                            Facing::Left => Direction::Left,
+                            Facing::Right => Direction::Right,
+                        },
+                    },
+                ));
+            }
+        } else {
+            let destination = Vec2::new(
+                rng.random_range(FLOOR_MIN.x..=FLOOR_MAX.x),
+                rng.random_range(FLOOR_MIN.y..=FLOOR_MAX.y),
+            );
+            if let Some(route) = graph.find_path(position, destination) {
+                commands.entity(entity).insert(Route(route));
+            }
+        }
+    }
+}
+
+// Walk the characters along their routes, steering away from each other.
+fn handle_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut trigger_events: MessageWriter<Trigger>,
+    mut query: Query<(Entity, &mut Transform, &State, &Direction, &mut Route), With<Npc>>,
+    others: Query<(Entity, &Transform), (With<Npc>, Without<Route>)>,
+) {
+    let positions: Vec<_> = query
+        .iter()
+        .map(|(entity, transform, ..)| (entity, transform.translation.truncate()))
+        .chain(
+            others
+                .iter()
+                .map(|(entity, transform)| (entity, transform.translation.truncate())),
+        )
+        .collect();
+
+    for (entity, mut transform, state, direction, mut route) in &mut query {
+        let position = transform.translation.truncate();
+        let Some(waypoint) = route.0.front().copied() else {
+            commands.entity(entity).remove::<Route>();
+            trigger_events.write(Trigger {
+                entity,
+                state: State::Idle,
+                direction: *direction,
+            });
+            continue;
+        };
+
+        // Push away from any other characters that are too close.
+        let avoidance: Vec2 = positions
+            .iter()
+            .filter(|(other, _)| *other != entity)
+            .map(|(_, other)| position - *other)
+            .filter(|offset| offset.length() < AVOID_RADIUS)
+            .map(|offset| offset.normalize_or_zero() * (1.0 - offset.length() / AVOID_RADIUS))
+            .sum();
+
+        let offset = waypoint - position;
+        let velocity = offset.normalize_or_zero() * WALKING_SPEED + avoidance * AVOID_STRENGTH;
+        let step = velocity * time.delta_secs();
+
+        let heading = if velocity.x < -f32::EPSILON {
+            Direction::Left
+        } else if velocity.x > f32::EPSILON {
+            Direction::Right
+        } else {
+            *direction
+        };
+        if *state != State::Walking || heading != *direction {
+            trigger_events.write(Trigger {
+                entity,
+                state: State::Walking,
+                direction: heading,
+            });
+        }
+
+        if offset.length() <= step.length().max(ARRIVE_DISTANCE) {
+            transform.translation = waypoint.extend(transform.translation.z);
+            route.0.pop_front();
+        } else {
+            transform.translation += step.extend(0.0);
+        }
+    }
+}
+
+// Use the prop once a character reaches it.
+fn handle_errands(
+    mut commands: Commands,
+    mut trigger_events: MessageWriter<Trigger>,
+    mut interaction_events: MessageWriter<InteractionEvent>,
+    query: Query<(Entity, &Errand), (With<Npc>, Without<Route>)>,
+) {
+    for (entity, errand) in &query {
+        trigger_events.write(Trigger {
+            entity,
+            state: State::Idle,
+            direction: errand.facing,
+        });
+        interaction_events.write(InteractionEvent {
+            id: errand.id.clone(),
+            verb: Verb::Use,
+        });
+        commands.entity(entity).remove::<Errand>();
+    }
+}
+
+// Stop and face whoever starts talking to a character.
+fn handle_interaction(
+    mut commands: Commands,
+    mut events: MessageReader<InteractionEvent>,
+    mut trigger_events: MessageWriter<Trigger>,
+    query: Query<(Entity, &Transform, &Interactable, &Direction), With<Npc>>,
+    interactors: Query<&Transform, (With<Interactor>, Without<Npc>)>,
+) {
+    for event in events.read() {
+        for (entity, transform, interactable, direction) in &query {
+            if event.id != interactable.id {
+                continue;
+            }
+
+            let facing = interactors
+                .iter()
+                .min_by(|a, b| {
+                    a.translation
+                        .distance_squared(transform.translation)
+                        .total_cmp(&b.translation.distance_squared(transform.translation))
+                })
+                .map_or(*direction, |interactor| {
+                    if interactor.translation.x < transform.translation.x {
+                        Direction::Left
+                    } else {
+                        Direction::Right
+                    }
+                });
+
+            commands
+                .entity(entity)
+                .remove::<(Route, Errand)>()
+                .insert(WanderTimer(Timer::from_seconds(TALK_DURATION, TimerMode::Once)));
+            trigger_events.write(Trigger {
+                entity,
+                state: State::Talking,
+                direction: facing,
+            });
+        }
+    }
+}
+
+// Go back to wandering once a conversation is over.
+fn handle_talking(
+    time: Res<Time>,
+    mut trigger_events: MessageWriter<Trigger>,
+    mut query: Query<(Entity, &mut WanderTimer, &State, &Direction), With<Npc>>,
+) {
+    for (entity, mut timer, state, direction) in &mut query {
+        if *state != State::Talking {
+            continue;
+        }
+
+        timer.0.tick(time.delta());
+        if timer.0.just_finished() {
+            timer.0 = Timer::from_seconds(WANDER_DELAY_MIN, TimerMode::Once);
+            trigger_events.write(Trigger {
+                entity,
+                state: State::Idle,
+                direction: *direction,
+            });
+        }
+    }
+}
+
+// Read animation messages and update each character's sprite.
+fn trigger_animation(
+    mut events: MessageReader<Trigger>,
+    sprites: Res<CharacterSprites>,
+    mut query: Query<(&mut Sprite, &mut State, &mut Direction), With<Npc>>,
+) {
+    for event in events.read() {
+        let Ok((mut sprite, mut state, mut direction)) = query.get_mut(event.entity) else {
+            continue;
+        };
+
+        // Only update if state changed
+        if *state == event.state && *direction == event.direction {
+            continue;
+        }
+
+        let facing_left = event.direction == Direction::Left;
+        match event.state {
+            State::Idle | State::Talking => sprites.stand(&mut sprite, facing_left),
+            State::Walking => sprites.walk(&mut sprite, facing_left),
+        }
+
+        *state = event.state;
+        *direction = event.direction;
+    }
+}
+
+// Initialize the characters.
+fn init(mut commands: Commands, sprites: Res<CharacterSprites>) {
+    let mut rng = rand::rng();
+
+    // Create the characters standing around.
+    for (name, x, color) in CHARACTERS {
+        let mut sprite = Sprite { color, ..default() };
+        sprites.stand(&mut sprite, false);
+
+        commands.spawn((
+            sprite,
+            Transform::from_scale(Vec3::splat(SPRITE_SCALE)).with_translation(Vec3::new(x, -76.0, 9.0)),
+            Npc,
+            AnimationConfig::new(0, WALKING_LAST_FRAME, 8),
+            State::Idle,
+            Direction::Right,
+            WanderTimer(Timer::from_seconds(
+                rng.random_range(WANDER_DELAY_MIN..=WANDER_DELAY_MAX),
+                TimerMode::Once,
+            )),
+            Interactable {
+                id: format!("npc:{name}"),
+                height: SPRITE_SIZE * SPRITE_SCALE,
+                width: SPRITE_SIZE / 2. * SPRITE_SCALE,
+                first: false,
+                stand_offset: -TALK_DISTANCE,
+                facing: Facing::Right,
+            },
+        ));
+    }
+}
@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use rand::{Rng, rng};
+use std::f32::consts::TAU;
+use std::ops::RangeInclusive;
+
+// Add to an entity to give off particles around it. Particles are left in the world once let off, so
+// they carry on if the emitter moves or goes away.
+#[derive(Clone, Component)]
+#[require(Transform, EmitterState)]
+pub struct ParticleEmitter {
+    // Particles let off every second.
+    pub rate: f32,
+    // Particles let off at once when the emitter is added.
+    pub burst: usize,
+    // Seconds each particle lasts.
+    pub lifetime: RangeInclusive<f32>,
+    pub shape: SpawnShape,
+    // Starting speed across and up, per second.
+    pub velocity_x: RangeInclusive<f32>,
+    pub velocity_y: RangeInclusive<f32>,
+    // Steady change in velocity, like gravity or rising heat, per second.
+    pub acceleration: Vec2,
+    // How far across particles sway back and forth, per second.
+    pub drift: RangeInclusive<f32>,
+    // Colour at the start and end of a particle's life, scaled by an opacity picked for each particle.
+    pub start_color: Color,
+    pub end_color: Color,
+    pub opacity: RangeInclusive<f32>,
+    // How much particles' opacity jumps around from frame to frame, from 0 steady to 1 winking out.
+    pub flicker: f32,
+    // Size at the start and end of a particle's life.
+    pub start_size: f32,
+    pub end_size: f32,
+    // Particles falling below this height are cleared early, like snow reaching the ground.
+    pub floor: Option<f32>,
+    // Start as if the emitter had been running all along, rather than empty.
+    pub prewarm: bool,
+}
+
+// Message sent to let off a number of particles from an emitter at once.
+#[derive(Message)]
+pub struct ParticleBurst {
+    pub emitter: Entity,
+    pub count: usize,
+}
+
+// Where around the emitter particles start.
+#[derive(Clone, Copy)]
+pub enum SpawnShape {
+    Point,
+    // Anywhere in a rectangle of this size, centred on the emitter.
+    Rect(Vec2),
+}
+
+// How far an emitter is through letting off its particles.
+#[derive(Component, Default)]
+struct EmitterState {
+    started: bool,
+    // Particles owed from bursts and earlier frames.
+    owed: f32,
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    acceleration: Vec2,
+    drift: f32,
+    drift_phase: f32,
+    age: f32,
+    lifetime: f32,
+    start_color: Color,
+    end_color: Color,
+    opacity: f32,
+    flicker: f32,
+    start_size: f32,
+    end_size: f32,
+    floor: Option<f32>,
+}
+
+// Add the particle systems.
+pub fn add_systems(app: &mut App) {
+    app.add_message::<ParticleBurst>()
+        .add_systems(Update, handle_particles)
+        // After transforms so particles start from where their emitter is this frame.
+        .add_systems(PostUpdate, handle_emitters.after(TransformSystems::Propagate));
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        ParticleEmitter {
+            rate: 10.0,
+            burst: 0,
+            lifetime: 1.0..=1.0,
+            shape: SpawnShape::Point,
+            velocity_x: 0.0..=0.0,
+            velocity_y: 0.0..=0.0,
+            acceleration: Vec2::ZERO,
+            drift: 0.0..=0.0,
+            start_color: Color::WHITE,
+            end_color: Color::WHITE,
+            opacity: 1.0..=1.0,
+            flicker: 0.0,
+            start_size: 1.0,
+            end_size: 1.0,
+            floor: None,
+            prewarm: false,
+        }
+    }
+}
+
+impl SpawnShape {
+    // A random point in the shape, relative to its centre.
+    fn sample(self, rng: &mut impl Rng) -> Vec2 {
+        match self {
+            SpawnShape::Point => Vec2::ZERO,
+            SpawnShape::Rect(size) => {
+                let half = size / 2.0;
+                Vec2::new(rng.random_range(-half.x..=half.x), rng.random_range(-half.y..=half.y))
+            }
+        }
+    }
+}
+
+impl Particle {
+    // Where the particle has got to from its start after some time, leaving out the sway.
+    fn travelled(&self, time: f32) -> Vec2 {
+        self.velocity * time + 0.5 * self.acceleration * time * time
+    }
+
+    fn progress(&self) -> f32 {
+        (self.age / self.lifetime).clamp(0.0, 1.0)
+    }
+
+    fn color(&self, rng: &mut impl Rng) -> Color {
+        let color = self.start_color.mix(&self.end_color, self.progress());
+        let flicker = rng.random_range(1.0 - self.flicker..=1.0);
+        color.with_alpha(color.alpha() * self.opacity * flicker)
+    }
+
+    fn size(&self) -> f32 {
+        self.start_size.lerp(self.end_size, self.progress())
+    }
+}
+
+// Let off particles from each emitter, all at once when it starts or bursts and then steadily.
+fn handle_emitters(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut burst_events: MessageReader<ParticleBurst>,
+    mut emitters: Query<(&ParticleEmitter, &mut EmitterState, &GlobalTransform)>,
+) {
+    let mut rng = rng();
+
+    for event in burst_events.read() {
+        if let Ok((_, mut state, _)) = emitters.get_mut(event.emitter) {
+            state.owed += event.count as f32;
+        }
+    }
+
+    for (emitter, mut state, transform) in &mut emitters {
+        let mut count = emitter.rate * time.delta_secs() + state.owed;
+        let mut prewarm = false;
+        if !state.started {
+            state.started = true;
+            count += emitter.burst as f32;
+
+            // Enough to fill a lifetime, each part way through its own.
+            if emitter.prewarm {
+                count += emitter.rate * (emitter.lifetime.start() + emitter.lifetime.end()) / 2.0;
+                prewarm = true;
+            }
+        }
+        state.owed = count.fract();
+
+        for _ in 0..count as usize {
+            let lifetime = rng.random_range(emitter.lifetime.clone());
+            let mut particle = Particle {
+                velocity: Vec2::new(
+                    rng.random_range(emitter.velocity_x.clone()),
+                    rng.random_range(emitter.velocity_y.clone()),
+                ),
+                acceleration: emitter.acceleration,
+                drift: rng.random_range(emitter.drift.clone()),
+                drift_phase: rng.random_range(0.0..TAU),
+                age: if prewarm { rng.random_range(0.0..=lifetime) } else { 0.0 },
+                lifetime,
+                start_color: emitter.start_color,
+                end_color: emitter.end_color,
+                opacity: rng.random_range(emitter.opacity.clone()),
+                flicker: emitter.flicker,
+                start_size: emitter.start_size,
+                end_size: emitter.end_size,
+                floor: emitter.floor,
+            };
+
+            let position =
+                transform.translation().truncate() + emitter.shape.sample(&mut rng) + particle.travelled(particle.age);
+            if particle.floor.is_some_and(|floor| position.y < floor) {
+                continue;
+            }
+            particle.velocity += particle.acceleration * particle.age;
+
+            // Placed in the world straight away, so they don't show up at the origin for a frame.
+            let transform = Transform::from_translation(position.extend(transform.translation().z));
+            commands.spawn((
+                Sprite {
+                    color: particle.color(&mut rng),
+                    custom_size: Some(Vec2::splat(particle.size())),
+                    ..default()
+                },
+                transform,
+                GlobalTransform::from(transform),
+                particle,
+            ));
+        }
+    }
+}
+
+// Move particles along, changing their colour and size as they age, and clear them away at the end.
+fn handle_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let mut rng = rng();
+    let delta = time.delta_secs();
+
+    for (entity, mut particle, mut transform, mut sprite) in &mut particles {
+        particle.age += delta;
+
+        let acceleration = particle.acceleration;
+        particle.velocity += acceleration * delta;
+        let sway = (particle.age + particle.drift_phase).sin() * particle.drift;
+        transform.translation.x += (particle.velocity.x + sway) * delta;
+        transform.translation.y += particle.velocity.y * delta;
+
+        if particle.age >= particle.lifetime || particle.floor.is_some_and(|floor| transform.translation.y < floor) {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        sprite.color = particle.color(&mut rng);
+        sprite.custom_size = Some(Vec2::splat(particle.size()));
+    }
+}
@@ -0,0 +1,350 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::{
+    animation::AnimationConfig,
+    fireplace::{self, Fireplace},
+    interaction::{Facing, Interactable, InteractionEvent, Underfoot, Verb},
+    navigation::NavGraph,
+    soundbank::PlaySound,
+    stereo::{self, Stereo},
+    theman::TheMan,
+};
+
+#[derive(Clone, Component, Copy, PartialEq)]
+enum State {
+    Sitting,
+    Walking,
+    Lying,
+    Petted,
+}
+
+#[derive(Clone, Component, Copy, PartialEq)]
+enum Direction {
+    Left,
+    Right,
+}
+
+#[derive(Message)]
+struct Trigger {
+    state: State,
+    direction: Direction,
+}
+
+#[derive(Component)]
+struct PetTimer(Timer);
+
+#[derive(Component)]
+struct Pet;
+
+// Waypoints the pet is walking through, and where they lead, so it can change course when the man moves on.
+#[derive(Component, Default)]
+struct Route {
+    waypoints: VecDeque<Vec2>,
+    target: Vec2,
+}
+
+const SPRITE_SCALE: f32 = 1.5;
+const SPRITE_WIDTH: f32 = 16.;
+const SPRITE_HEIGHT: f32 = 12.;
+
+const SIT_FRAME: usize = 4;
+const LIE_FRAME: usize = 5;
+
+const WALKING_SPEED: f32 = 34.0;
+const FOLLOW_DISTANCE: f32 = 24.0;
+const FOLLOW_SLACK: f32 = 10.0;
+const FEET_OFFSET: f32 = -13.0;
+const RETARGET_DISTANCE: f32 = 4.0;
+
+const FIRE_SPOT: Vec2 = Vec2::new(16.0, -86.0);
+const DANCE_SPEED: f32 = 9.0;
+
+const PET_DURATION: f32 = 1.8;
+
+const STAND_OFFSET: f32 = -18.0;
+const INTERACTABLE_ID: &str = "pet";
+
+// Add the pet systems.
+pub fn add_systems(app: &mut App) {
+    app.add_message::<Trigger>().add_systems(Startup, init).add_systems(
+        Update,
+        (
+            handle_animations,
+            handle_movement,
+            handle_interaction,
+            handle_petting,
+            handle_stereo,
+            trigger_animation,
+        ),
+    );
+}
+
+// Advance the walk cycle, and bounce along when the stereo is playing.
+fn handle_animations(
+    time: Res<Time>,
+    mut query: Query<(&mut AnimationConfig, &mut Sprite, &State), With<Pet>>,
+    stereos: Query<&stereo::State, With<Stereo>>,
+) {
+    let music = stereos.iter().any(|state| *state == stereo::State::Running);
+
+    for (mut config, mut sprite, state) in &mut query {
+        // Hop between sitting and standing in time with the music.
+        if *state == State::Sitting
+            && let Some(atlas) = &mut sprite.texture_atlas
+        {
+            atlas.index = if music && (time.elapsed_secs() * DANCE_SPEED).sin() > 0. {
+                config.first_index
+            } else {
+                SIT_FRAME
+            };
+        }
+
+        if *state == State::Walking
+            && let Some(atlas) = &mut sprite.texture_atlas
+        {
+            config.advance(time.delta(), atlas, true);
+        }
+    }
+}
+
+// Follow the man at a distance, or curl up by the fireplace while it's lit, finding a way around the furniture.
+fn handle_movement(
+    time: Res<Time>,
+    graph: Res<NavGraph>,
+    mut trigger_events: MessageWriter<Trigger>,
+    mut query: Query<(&mut Transform, &mut Route, &State, &Direction), With<Pet>>,
+    theman: Query<(&Transform, &Sprite), (With<TheMan>, Without<Pet>)>,
+    fireplaces: Query<&fireplace::State, With<Fireplace>>,
+    stereos: Query<&stereo::State, With<Stereo>>,
+) {
+    let fire = fireplaces.iter().any(|state| *state == fireplace::State::Running);
+    let music = stereos.iter().any(|state| *state == stereo::State::Running);
+
+    for (mut transform, mut route, state, direction) in &mut query {
+        if *state == State::Petted {
+            continue;
+        }
+
+        // Stay behind the man, on the side he's walking away from.
+        let (target, slack) = if fire {
+            (FIRE_SPOT, 0.0)
+        } else if let Ok((man_transform, man_sprite)) = theman.single() {
+            let behind = if man_sprite.flip_x {
+                FOLLOW_DISTANCE
+            } else {
+                -FOLLOW_DISTANCE
+            };
+            (
+                man_transform.translation.truncate() + Vec2::new(behind, FEET_OFFSET),
+                FOLLOW_SLACK,
+            )
+        } else {
+            continue;
+        };
+
+        let target = graph.clamp(target);
+        let position = transform.translation.truncate();
+        let distance = position.distance(target);
+        let step = WALKING_SPEED * time.delta_secs();
+        let rest = if fire && !music { State::Lying } else { State::Sitting };
+
+        // Close enough, settle down.
+        if distance <= slack.max(step) {
+            if slack == 0.0 {
+                transform.translation = target.extend(transform.translation.z);
+            }
+            route.waypoints.clear();
+
+            if *state != rest {
+                trigger_events.write(Trigger {
+                    state: rest,
+                    direction: *direction,
+                });
+            }
+            continue;
+        }
+
+        // Only start moving once the target is well out of reach.
+        if *state != State::Walking && distance <= slack * 2.0 {
+            continue;
+        }
+
+        // Find a new way when the target has moved on, and sit tight if there isn't one.
+        if route.waypoints.is_empty() || route.target.distance(target) > RETARGET_DISTANCE {
+            route.target = target;
+            route.waypoints = graph.find_path(position, target).unwrap_or_default();
+            // Somewhere blocked that it can't get any closer to counts as no way there.
+            if route.waypoints.back().is_some_and(|end| end.distance(position) <= step) {
+                route.waypoints.clear();
+            }
+        }
+        let Some(waypoint) = route.waypoints.front().copied() else {
+            if *state != rest {
+                trigger_events.write(Trigger {
+                    state: rest,
+                    direction: *direction,
+                });
+            }
+            continue;
+        };
+
+        // Face the way the pet is walking, keeping the current facing on vertical moves.
+        let offset = waypoint - position;
+        let heading = if offset.x < -f32::EPSILON {
+            Direction::Left
+        } else if offset.x > f32::EPSILON {
+            Direction::Right
+        } else {
+            *direction
+        };
+        if *state != State::Walking || heading != *direction {
+            trigger_events.write(Trigger {
+                state: State::Walking,
+                direction: heading,
+            });
+        }
+
+        if offset.length() <= step {
+            transform.translation = waypoint.extend(transform.translation.z);
+            route.waypoints.pop_front();
+        } else {
+            transform.translation += (offset.normalize() * step).extend(0.0);
+        }
+    }
+}
+
+// Purr, or sometimes meow, when petted.
+fn handle_interaction(
+    mut commands: Commands,
+    mut events: MessageReader<InteractionEvent>,
+    mut sound_events: MessageWriter<PlaySound>,
+    mut trigger_events: MessageWriter<Trigger>,
+    query: Single<(Entity, &Direction), With<Pet>>,
+) {
+    let (entity, direction) = query.into_inner();
+
+    for event in events.read() {
+        if event.id != INTERACTABLE_ID || event.verb != Verb::Use {
+            continue;
+        }
+
+        sound_events.write(PlaySound {
+            bank: "pet/petted".to_string(),
+            emitter: Some(entity),
+        });
+        commands
+            .entity(entity)
+            .insert(PetTimer(Timer::from_seconds(PET_DURATION, TimerMode::Once)));
+        trigger_events.write(Trigger {
+            state: State::Petted,
+            direction: *direction,
+        });
+    }
+}
+
+// Wiggle happily while being petted, then go back to following.
+fn handle_petting(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut trigger_events: MessageWriter<Trigger>,
+    mut query: Query<(Entity, &mut PetTimer, &mut Transform, &Direction), With<Pet>>,
+) {
+    for (entity, mut timer, mut transform, direction) in &mut query {
+        timer.0.tick(time.delta());
+
+        if timer.0.is_finished() {
+            transform.scale = Vec3::splat(SPRITE_SCALE);
+            commands.entity(entity).remove::<PetTimer>();
+            trigger_events.write(Trigger {
+                state: State::Sitting,
+                direction: *direction,
+            });
+        } else {
+            let wiggle = (timer.0.elapsed_secs() * DANCE_SPEED * 2.).sin().mul_add(0.04, 1.);
+            transform.scale = Vec3::new(SPRITE_SCALE, SPRITE_SCALE * wiggle, SPRITE_SCALE);
+        }
+    }
+}
+
+// Meow when the music starts.
+fn handle_stereo(
+    mut sound_events: MessageWriter<PlaySound>,
+    stereos: Query<&stereo::State, (With<Stereo>, Changed<stereo::State>)>,
+    query: Single<Entity, With<Pet>>,
+) {
+    let entity = query.into_inner();
+
+    for state in &stereos {
+        if *state == stereo::State::Running {
+            sound_events.write(PlaySound {
+                bank: "pet/meow".to_string(),
+                emitter: Some(entity),
+            });
+        }
+    }
+}
+
+// Read animation messages and update the pet's sprite.
+fn trigger_animation(
+    mut events: MessageReader<Trigger>,
+    query: Single<(&mut AnimationConfig, &mut Sprite, &mut State, &mut Direction), With<Pet>>,
+) {
+    let (mut config, mut sprite, mut state, mut direction) = query.into_inner();
+    for event in events.read() {
+        // Only update if state changed
+        if *state == event.state && *direction == event.direction {
+            continue;
+        }
+
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = match event.state {
+                State::Sitting | State::Petted => SIT_FRAME,
+                State::Lying => LIE_FRAME,
+                State::Walking => config.first_index,
+            };
+        }
+        if event.state == State::Walking {
+            config.frame_timer = AnimationConfig::timer_from_fps(config.fps);
+        }
+        sprite.flip_x = event.direction == Direction::Left;
+
+        *state = event.state;
+        *direction = event.direction;
+    }
+}
+
+// Initialize the pet.
+fn init(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    // Create the pet sitting next to the man.
+    commands.spawn((
+        Sprite {
+            image: asset_server.load("pet/cat.png"),
+            texture_atlas: Some(TextureAtlas {
+                layout: texture_layouts.add(TextureAtlasLayout::from_grid(UVec2::splat(16), 6, 1, None, None)),
+                index: SIT_FRAME,
+            }),
+            ..default()
+        },
+        Transform::from_scale(Vec3::splat(SPRITE_SCALE)).with_translation(Vec3::new(-88.0, -86.0, 11.0)),
+        Pet,
+        Route::default(),
+        AnimationConfig::new(0, 3, 10),
+        State::Sitting,
+        Direction::Right,
+        Interactable {
+            id: INTERACTABLE_ID.to_string(),
+            height: SPRITE_HEIGHT * SPRITE_SCALE,
+            width: SPRITE_WIDTH * SPRITE_SCALE,
+            first: false,
+            stand_offset: STAND_OFFSET,
+            facing: Facing::Right,
+        },
+        // The cat is always at the man's feet, so it shouldn't get in the way of the props.
+        Underfoot,
+    ));
+}
@@ -0,0 +1,123 @@
+use bevy::{asset::LoadContext, prelude::*};
+
+use crate::{
+    lyrics::Lyrics,
+    stream::StreamedAudio,
+    synth::Song,
+    text_asset::{TextAsset, TextAssetError, TextAssetLoader},
+};
+
+// A list of tracks for the stereo loaded from a `.playlist` file.
+//
+// Each line is `Title = file`, or `Title = file, lyrics.lrc` for a track to sing along to, with the files
+// relative to the playlist. Files can be recorded audio or `.song` files for the synthesizer. `shuffle: yes|no` and
+// `repeat: off|one|all` set how the tracks play to begin with.
+#[derive(Asset, TypePath)]
+pub struct Playlist {
+    pub tracks: Vec<PlaylistTrack>,
+    pub shuffle: bool,
+    pub repeat: Repeat,
+}
+
+pub struct PlaylistTrack {
+    pub title: String,
+    pub sound: TrackSound,
+    pub lyrics: Option<Handle<Lyrics>>,
+}
+
+// What plays for a track.
+pub enum TrackSound {
+    // Recordings are long, so they're streamed rather than loaded up front.
+    Streamed(Handle<StreamedAudio>),
+    // Songs are tiny, and played by the built-in synthesizer.
+    Song(Handle<Song>),
+}
+
+// What happens when a track finishes.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Repeat {
+    // Play through the list once and stop.
+    Off,
+    One,
+    All,
+}
+
+// Add the playlist systems.
+pub fn add_systems(app: &mut App) {
+    app.init_asset::<Playlist>()
+        .init_asset_loader::<TextAssetLoader<Playlist>>();
+}
+
+impl Repeat {
+    // The mode after this one, for cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            Repeat::Off => Repeat::All,
+            Repeat::All => Repeat::One,
+            Repeat::One => Repeat::Off,
+        }
+    }
+}
+
+impl TextAsset for Playlist {
+    const EXTENSIONS: &'static [&'static str] = &["playlist"];
+
+    // Parse a playlist, loading its tracks alongside it.
+    fn from_text(source: &str, load_context: &mut LoadContext<'_>) -> Result<Self, TextAssetError> {
+        let mut playlist = Playlist {
+            tracks: Vec::new(),
+            shuffle: false,
+            repeat: Repeat::All,
+        };
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: &str| TextAssetError::at(index + 1, message);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some((title, paths)) = line.split_once('=') {
+                let resolve = |path: &str| {
+                    load_context
+                        .asset_path()
+                        .resolve_embed(path.trim())
+                        .map_err(|_| error("invalid track path"))
+                };
+                let (sound, lyrics) = match paths.split_once(',') {
+                    Some((sound, lyrics)) => (resolve(sound)?, Some(resolve(lyrics)?)),
+                    None => (resolve(paths)?, None),
+                };
+                playlist.tracks.push(PlaylistTrack {
+                    title: title.trim().to_string(),
+                    sound: if sound.get_full_extension().as_deref() == Some("song") {
+                        TrackSound::Song(load_context.load(sound))
+                    } else {
+                        TrackSound::Streamed(load_context.load(sound))
+                    },
+                    lyrics: lyrics.map(|lyrics| load_context.load(lyrics)),
+                });
+                continue;
+            }
+
+            let (setting, value) = line
+                .split_once(':')
+                .ok_or_else(|| error("expected 'Title = file' or a setting"))?;
+            match (setting.trim(), value.trim()) {
+                ("shuffle", "yes") => playlist.shuffle = true,
+                ("shuffle", "no") => playlist.shuffle = false,
+                ("repeat", "off") => playlist.repeat = Repeat::Off,
+                ("repeat", "one") => playlist.repeat = Repeat::One,
+                ("repeat", "all") => playlist.repeat = Repeat::All,
+                ("shuffle" | "repeat", _) => return Err(error("unknown mode")),
+                _ => return Err(error("unknown setting")),
+            }
+        }
+
+        if playlist.tracks.is_empty() {
+            return Err(TextAssetError::at(source.lines().count(), "playlist has no tracks"));
+        }
+        Ok(playlist)
+    }
+}
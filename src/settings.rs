@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+
+use crate::{notice::Notice, theman::TheMan};
+
+// Preferences for how the scene plays, changed with keys.
+#[derive(Default, Resource)]
+pub struct Settings {
+    // Hear the scene from the camera rather than from the man, so sounds pan with the screen.
+    pub listener_on_camera: bool,
+    // Describe sounds on screen as they play.
+    pub captions: bool,
+}
+
+// Add the settings systems.
+pub fn add_systems(app: &mut App) {
+    app.init_resource::<Settings>()
+        .add_systems(Update, (handle_keys, handle_listener));
+}
+
+// L moves the listener between the man and the camera, C turns captions on and off.
+fn handle_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut notices: MessageWriter<Notice>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyL) {
+        settings.listener_on_camera = !settings.listener_on_camera;
+        notices.write(Notice(format!(
+            "Listening from the {}",
+            if settings.listener_on_camera { "camera" } else { "man" }
+        )));
+    }
+    if keyboard.just_pressed(KeyCode::KeyC) {
+        settings.captions = !settings.captions;
+        notices.write(Notice(format!(
+            "Captions {}",
+            if settings.captions { "on" } else { "off" }
+        )));
+    }
+}
+
+// Move the listener to wherever the settings put it.
+fn handle_listener(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    listener: Single<(Entity, &SpatialListener)>,
+    man: Single<Entity, With<TheMan>>,
+    camera: Single<Entity, With<Camera2d>>,
+) {
+    let target = if settings.listener_on_camera { *camera } else { *man };
+    let (entity, spatial_listener) = *listener;
+    if entity != target {
+        commands.entity(entity).remove::<SpatialListener>();
+        commands.entity(target).insert(spatial_listener.clone());
+    }
+}
@@ -0,0 +1,322 @@
+use bevy::{asset::LoadContext, audio::Volume, ecs::system::SystemParam, prelude::*};
+use rand::{Rng, rng};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use crate::{
+    acoustics::RoomSound,
+    captions::Caption,
+    mixer::{Bus, Ducking},
+    text_asset::{TextAsset, TextAssetError, TextAssetLoader},
+};
+
+// A set of interchangeable sounds loaded from a `.bank` file.
+//
+// Each line names a clip relative to the bank file, optionally followed by a weight (`meow.wav 3`).
+// `volume: min max` and `pitch: min max` pick a random volume and playback speed for every play,
+// `duck: yes` lowers the music while it plays, and `caption: text` describes it in closed captions. The
+// same clip never plays twice in a row unless it's the only one.
+#[derive(Asset, TypePath)]
+pub struct SoundBank {
+    clips: Vec<Clip>,
+    volume: RangeInclusive<f32>,
+    pitch: RangeInclusive<f32>,
+    duck: bool,
+    caption: Option<String>,
+}
+
+struct Clip {
+    sound: Handle<AudioSource>,
+    weight: u32,
+}
+
+// Message sent to play a sound from the bank at `assets/<bank>.bank`, from an emitter entity if given.
+#[derive(Message)]
+pub struct PlaySound {
+    pub bank: String,
+    pub emitter: Option<Entity>,
+}
+
+// Banks loaded so far by name, and the clip each one played last.
+#[derive(Default, Resource)]
+struct SoundBanks {
+    handles: HashMap<String, Handle<SoundBank>>,
+    last: HashMap<String, usize>,
+}
+
+// The clips banks play, and the sounds played from them in their rooms.
+#[derive(SystemParam)]
+struct Clips<'w> {
+    sources: Res<'w, Assets<AudioSource>>,
+    room_sounds: ResMut<'w, Assets<RoomSound>>,
+}
+
+// Sounds waiting on their bank to finish loading.
+#[derive(Default, Resource)]
+struct PendingSounds(Vec<PlaySound>);
+
+// Add the sound bank systems.
+pub fn add_systems(app: &mut App) {
+    app.init_asset::<SoundBank>()
+        .init_asset_loader::<TextAssetLoader<SoundBank>>()
+        .init_resource::<SoundBanks>()
+        .init_resource::<PendingSounds>()
+        .add_message::<PlaySound>()
+        .add_systems(Update, handle_play);
+}
+
+impl TextAsset for SoundBank {
+    const EXTENSIONS: &'static [&'static str] = &["bank"];
+
+    // Load the bank's clips alongside it, relative to the bank file.
+    fn from_text(source: &str, load_context: &mut LoadContext<'_>) -> Result<Self, TextAssetError> {
+        SoundBank::parse(source, |path| {
+            let path = load_context.asset_path().resolve_embed(path).ok()?;
+            Some(load_context.load(path))
+        })
+    }
+}
+
+impl SoundBank {
+    // Parse a bank, loading each clip it names with `load`, which gives nothing for a bad path.
+    fn parse(source: &str, mut load: impl FnMut(&str) -> Option<Handle<AudioSource>>) -> Result<Self, TextAssetError> {
+        let mut bank = SoundBank {
+            clips: Vec::new(),
+            volume: 1.0..=1.0,
+            pitch: 1.0..=1.0,
+            duck: false,
+            caption: None,
+        };
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: &str| TextAssetError::at(index + 1, message);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some((setting, value)) = line.split_once(':') {
+                let range = || parse_range(value).ok_or_else(|| error("expected 'min max'"));
+                match setting.trim() {
+                    "volume" => bank.volume = range()?,
+                    "pitch" => bank.pitch = range()?,
+                    "caption" => bank.caption = Some(value.trim().to_string()),
+                    "duck" => {
+                        bank.duck = match value.trim() {
+                            "yes" => true,
+                            "no" => false,
+                            _ => return Err(error("expected 'yes' or 'no'")),
+                        }
+                    }
+                    _ => return Err(error("unknown setting")),
+                }
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let path = parts.next().ok_or_else(|| error("expected a clip"))?;
+            let weight = match parts.next() {
+                Some(weight) => weight.parse().map_err(|_| error("weight must be a whole number"))?,
+                None => 1,
+            };
+            bank.clips.push(Clip {
+                sound: load(path).ok_or_else(|| error("invalid clip path"))?,
+                weight,
+            });
+        }
+
+        if bank.clips.iter().all(|clip| clip.weight == 0) {
+            return Err(TextAssetError::at(source.lines().count(), "bank has no clips to play"));
+        }
+        Ok(bank)
+    }
+
+    // Pick a weighted clip, avoiding the one played last when there's a choice.
+    fn choose(&self, last: Option<usize>) -> usize {
+        let weight = |index: usize| {
+            if Some(index) == last && self.clips.len() > 1 {
+                0
+            } else {
+                self.clips[index].weight
+            }
+        };
+
+        let total: u32 = (0..self.clips.len()).map(weight).sum();
+        if total == 0 {
+            return last.unwrap_or(0);
+        }
+
+        let mut roll = rng().random_range(0..total);
+        for index in 0..self.clips.len() {
+            if roll < weight(index) {
+                return index;
+            }
+            roll -= weight(index);
+        }
+        self.clips.len() - 1
+    }
+}
+
+// Read a `min max` pair, or a single fixed value.
+fn parse_range(source: &str) -> Option<RangeInclusive<f32>> {
+    let mut values = source.split_whitespace().map(str::parse::<f32>);
+    let min = values.next()?.ok()?;
+    let max = match values.next() {
+        Some(max) => max.ok()?,
+        None => min,
+    };
+    (min <= max && values.next().is_none()).then_some(min..=max)
+}
+
+// Play requested sounds once their banks have loaded.
+fn handle_play(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    sound_banks: Res<Assets<SoundBank>>,
+    mut clips: Clips,
+    mut banks: ResMut<SoundBanks>,
+    mut pending: ResMut<PendingSounds>,
+    mut events: MessageReader<PlaySound>,
+) {
+    let requests: Vec<PlaySound> = pending
+        .0
+        .drain(..)
+        .chain(events.read().map(|event| PlaySound {
+            bank: event.bank.clone(),
+            emitter: event.emitter,
+        }))
+        .collect();
+
+    for request in requests {
+        let handle = banks
+            .handles
+            .entry(request.bank.clone())
+            .or_insert_with(|| asset_server.load(format!("{}.bank", request.bank)))
+            .clone();
+
+        let Some(bank) = sound_banks.get(&handle) else {
+            // Give up on banks that will never load.
+            if !asset_server.load_state(&handle).is_failed() {
+                pending.0.push(request);
+            }
+            continue;
+        };
+
+        // The emitter went away while the bank was loading.
+        if request
+            .emitter
+            .is_some_and(|emitter| commands.get_entity(emitter).is_err())
+        {
+            continue;
+        }
+
+        // Sounds play with the acoustics of the room they're in, which needs the clip itself.
+        let index = bank.choose(banks.last.get(&request.bank).copied());
+        let clip = &bank.clips[index].sound;
+        let Some(source) = clips.sources.get(clip) else {
+            if !asset_server.load_state(clip).is_failed() {
+                pending.0.push(request);
+            }
+            continue;
+        };
+        banks.last.insert(request.bank.clone(), index);
+
+        let mut rng = rng();
+        let settings = PlaybackSettings::DESPAWN
+            .with_volume(Volume::Linear(rng.random_range(bank.volume.clone())))
+            .with_speed(rng.random_range(bank.pitch.clone()));
+        // Every play gets a room sound of its own, so it's heard with the acoustics of where it is. The sound
+        // is freed along with its player once it finishes.
+        let player = AudioPlayer(clips.room_sounds.add(RoomSound::new(source.clone())));
+
+        // Sounds from an emitter follow it around, others play everywhere.
+        let mut sound = commands.spawn((player, settings.with_spatial(request.emitter.is_some()), Bus::Sfx));
+        if let Some(emitter) = request.emitter {
+            sound.insert((ChildOf(emitter), Transform::default()));
+        }
+        if bank.duck {
+            sound.insert(Ducking);
+        }
+        if let Some(caption) = &bank.caption {
+            sound.insert(Caption(caption.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<SoundBank, TextAssetError> {
+        SoundBank::parse(source, |_| Some(Handle::default()))
+    }
+
+    // The line number of a parse error.
+    fn error_line(source: &str) -> usize {
+        match parse(source) {
+            Err(TextAssetError::Parse { line, .. }) => line,
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn parses_clips_and_settings() {
+        let bank =
+            parse("# Meows\na.wav\nb.wav 3\nvolume: 0.5 0.8\npitch: 1.2\nduck: yes\ncaption: cat meowing\n").unwrap();
+        assert_eq!(bank.clips.iter().map(|clip| clip.weight).collect::<Vec<_>>(), [1, 3]);
+        assert_eq!(bank.volume, 0.5..=0.8);
+        assert_eq!(bank.pitch, 1.2..=1.2);
+        assert!(bank.duck);
+        assert_eq!(bank.caption.as_deref(), Some("cat meowing"));
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        assert_eq!(error_line("a.wav\nb.wav lots\n"), 2);
+        assert_eq!(error_line("a.wav\n\n# Louder\nvolume: 1 0.5\n"), 4);
+        assert_eq!(error_line("a.wav\nduck: maybe\n"), 2);
+        assert_eq!(error_line("echo: lots\na.wav\n"), 1);
+        assert!(matches!(
+            SoundBank::parse("a.wav\n", |_| None),
+            Err(TextAssetError::Parse { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn needs_a_clip_to_play() {
+        assert_eq!(error_line("volume: 0.5\n"), 1);
+        assert_eq!(error_line("a.wav 0\nb.wav 0\n"), 2);
+    }
+
+    #[test]
+    fn never_repeats_a_clip() {
+        for source in ["a.wav\nb.wav\n", "a.wav\nb.wav 5\nc.wav\n"] {
+            let bank = parse(source).unwrap();
+            let mut last = None;
+            for _ in 0..1000 {
+                let index = bank.choose(last);
+                assert_ne!(Some(index), last);
+                last = Some(index);
+            }
+        }
+    }
+
+    #[test]
+    fn repeats_a_lone_clip() {
+        let bank = parse("a.wav\n").unwrap();
+        assert_eq!(bank.choose(None), 0);
+        assert_eq!(bank.choose(Some(0)), 0);
+    }
+
+    #[test]
+    fn follows_weights() {
+        let bank = parse("a.wav 1\nb.wav 9\nc.wav 0\n").unwrap();
+        let mut counts = [0; 3];
+        for _ in 0..10_000 {
+            counts[bank.choose(None)] += 1;
+        }
+        assert_eq!(counts[2], 0);
+        assert!((8_500..=9_500).contains(&counts[1]), "{counts:?}");
+    }
+}
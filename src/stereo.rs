@@ -1,12 +1,28 @@
-use bevy::{audio::Volume, prelude::*};
+use bevy::{
+    audio::{AudioSinkPlayback, Volume},
+    ecs::system::SystemParam,
+    prelude::*,
+    sprite::Anchor,
+};
+use rand::{rng, seq::SliceRandom};
 
 use crate::{
+    acoustics::Acoustic,
+    analysis::{Analysed, BAND_COUNT, Spectrum},
     animation::AnimationConfig,
-    interaction::{Highlight, Interactable, InteractionEvent},
+    bubble::{BubbleContent, BubbleRequest, BubbleStyle},
+    captions::Caption,
+    interaction::{Facing, Highlight, Interactable, InteractionEvent, Verb},
+    lyrics::Sung,
+    mixer::{Attenuation, Bus, Fade, FadeCurve},
+    navigation::Obstacle,
+    playlist::{Playlist, Repeat, TrackSound},
+    stream::StreamedAudio,
+    synth::Song,
 };
 
 #[derive(Clone, Component, Copy, PartialEq)]
-enum State {
+pub enum State {
     Off,
     Running,
 }
@@ -19,14 +35,70 @@ struct SpriteAssets {
 }
 
 #[derive(Component)]
-struct Stereo;
+pub struct Stereo;
+
+// The stereo's place in its playlist.
+#[derive(Component)]
+struct Playback {
+    playlist: Handle<Playlist>,
+    // Track indices in the order they play, empty until the playlist has loaded.
+    order: Vec<usize>,
+    position: usize,
+    shuffle: bool,
+    repeat: Repeat,
+    // The track entity playing now.
+    current: Option<Entity>,
+}
+
+// A track playing from the stereo.
+#[derive(Component)]
+struct Track;
+
+// Added to a skipped track while it fades out under the next one.
+#[derive(Component)]
+struct Ending;
+
+// One of the equalizer bars on the stereo's display, showing a band of the spectrum.
+#[derive(Component)]
+struct EqualizerBar(usize);
+
+// The caption showing what the stereo is playing.
+#[derive(Component)]
+struct NowPlaying;
+
+// The sounds tracks play, copied for each player.
+#[derive(SystemParam)]
+struct TrackSounds<'w> {
+    streams: ResMut<'w, Assets<StreamedAudio>>,
+    songs: ResMut<'w, Assets<Song>>,
+}
 
 const RUNNING_VOLUME: f32 = 0.9;
+const NOTE_DURATION: f32 = 2.0;
+const MUSIC_FADE_IN: f32 = 0.8;
+const MUSIC_FADE_OUT: f32 = 1.5;
+// The music carries through the house, and a little way outside.
+const MUSIC_MIN_DISTANCE: f32 = 64.0;
+const MUSIC_MAX_DISTANCE: f32 = 400.0;
+const MUSIC_ROLLOFF: f32 = 0.5;
+const CAPTION_SIZE: f32 = 14.0;
+// Beats per minute the tempo keys change a song by.
+const TEMPO_STEP: f32 = 10.0;
+
+// Where the equalizer sits on the stereo's panel, in sprite pixels from its center.
+const EQUALIZER_LEFT: f32 = 2.5;
+const EQUALIZER_BOTTOM: f32 = -13.0;
+const EQUALIZER_SPACING: f32 = 2.0;
+const EQUALIZER_HEIGHT: f32 = 5.0;
+const EQUALIZER_COLOR: Color = Color::srgb(0.4, 1.0, 0.55);
 
 const SPRITE_SCALE: f32 = 2.0;
 const SPRITE_WIDTH: f32 = 20.;
 const SPRITE_HEIGHT: f32 = 16.;
 
+const OBSTACLE_DEPTH: f32 = 6.0;
+const STAND_OFFSET: f32 = -30.0;
+
 const INTERACTABLE_ID: &str = "stereo";
 
 // Add the animation systems.
@@ -40,6 +112,12 @@ pub fn add_systems(app: &mut App) {
             handle_interaction,
             handle_interaction_disable_highlight,
             handle_sound,
+            handle_playback,
+            handle_skip,
+            handle_ending,
+            handle_keys,
+            handle_caption,
+            handle_equalizer,
         ),
     );
 }
@@ -107,6 +185,7 @@ fn handle_interaction(
 ) {
     for event in events.read() {
         if event.id == INTERACTABLE_ID
+            && event.verb == Verb::Use
             && let Ok((mut state, mut sprite)) = query.single_mut()
         {
             match *state {
@@ -119,16 +198,18 @@ fn handle_interaction(
                     });
                 }
 
-                State::Running => {
-                    *state = State::Off;
-                    sprite.image = sprite_assets.off_sprite.clone();
-                    sprite.texture_atlas = None;
-                }
+                State::Running => switch_off(&mut state, &mut sprite, &sprite_assets),
             }
         }
     }
 }
 
+fn switch_off(state: &mut State, sprite: &mut Sprite, sprite_assets: &SpriteAssets) {
+    *state = State::Off;
+    sprite.image = sprite_assets.off_sprite.clone();
+    sprite.texture_atlas = None;
+}
+
 fn handle_interaction_disable_highlight(
     mut query: Query<(&mut State, &mut Interactable), (With<Stereo>, Changed<State>)>,
 ) {
@@ -139,19 +220,293 @@ fn handle_interaction_disable_highlight(
     }
 }
 
-// Control audio playback based on stereo state
-fn handle_sound(query: Query<(&State, &mut SpatialAudioSink), (With<Stereo>, Changed<State>)>) {
-    for (state, audio_sink) in &query {
+impl Playback {
+    // Take the tracks and modes from a newly loaded playlist.
+    fn start(&mut self, playlist: &Playlist) {
+        self.order = (0..playlist.tracks.len()).collect();
+        self.position = 0;
+        self.repeat = playlist.repeat;
+        if playlist.shuffle {
+            self.set_shuffle(true);
+        }
+    }
+
+    // The playlist index of the current track.
+    fn track(&self) -> usize {
+        self.order[self.position]
+    }
+
+    // Shuffle the tracks or put them back in order, carrying on from the current one either way.
+    fn set_shuffle(&mut self, shuffle: bool) {
+        let current = self.track();
+        self.order.sort_unstable();
+        self.position = current;
+        if shuffle {
+            self.order.shuffle(&mut rng());
+            let at = self.order.iter().position(|&index| index == current).unwrap_or(0);
+            self.order.swap(0, at);
+            self.position = 0;
+        }
+        self.shuffle = shuffle;
+    }
+
+    // Move on to the next track, returning false when the playlist has run out.
+    fn advance(&mut self, skipped: bool) -> bool {
+        if self.repeat == Repeat::One && !skipped {
+            return true;
+        }
+
+        self.position += 1;
+        if self.position < self.order.len() {
+            return true;
+        }
+
+        // Wrap around, with a new order if shuffling. Skipping past the end always wraps.
+        self.position = 0;
+        if self.shuffle {
+            self.order.shuffle(&mut rng());
+        }
+        skipped || self.repeat != Repeat::Off
+    }
+}
+
+// Fade the music in and out based on stereo state
+fn handle_sound(
+    mut bubble_events: MessageWriter<BubbleRequest>,
+    query: Query<(Entity, &State, &Playback), (With<Stereo>, Changed<State>)>,
+    mut tracks: Query<&mut Fade, With<Track>>,
+) {
+    for (entity, state, playback) in &query {
+        let mut fade = playback.current.and_then(|current| tracks.get_mut(current).ok());
+
         match *state {
-            // Start the stereo sound effect if it isn't already running.
+            // Fade the music in, with a note popping up over the stereo.
             State::Running => {
-                audio_sink.play();
+                if let Some(fade) = &mut fade {
+                    fade.fade_in();
+                }
+                bubble_events.write(BubbleRequest {
+                    entity,
+                    content: BubbleContent::Icon("ui/note.png"),
+                    style: BubbleStyle::Speech,
+                    duration: NOTE_DURATION,
+                });
             }
 
-            // Remove any existing sound effects.
+            // Fade the music out, it's paused once silent.
             State::Off => {
-                audio_sink.pause();
+                if let Some(fade) = &mut fade {
+                    fade.fade_out();
+                }
+            }
+        }
+    }
+}
+
+// Start tracks while the stereo is on, moving through the playlist as each one finishes.
+fn handle_playback(
+    mut commands: Commands,
+    playlists: Res<Assets<Playlist>>,
+    mut sounds: TrackSounds,
+    sprite_assets: Res<SpriteAssets>,
+    mut stereos: Query<(Entity, &mut State, &mut Sprite, &mut Playback), With<Stereo>>,
+    tracks: Query<&SpatialAudioSink, With<Track>>,
+) {
+    for (entity, mut state, mut sprite, mut playback) in &mut stereos {
+        let Some(playlist) = playlists.get(&playback.playlist) else {
+            continue;
+        };
+        if playback.order.is_empty() {
+            playback.start(playlist);
+        }
+
+        if let Some(current) = playback.current
+            && tracks.get(current).is_ok_and(|sink| sink.empty())
+        {
+            commands.entity(current).despawn();
+            playback.current = None;
+
+            // Switch the stereo off straight away once the playlist runs out, so the first track doesn't start again.
+            if !playback.advance(false) {
+                switch_off(&mut state, &mut sprite, &sprite_assets);
+                continue;
+            }
+        }
+
+        if *state == State::Running && playback.current.is_none() {
+            let mut fade = Fade::new(FadeCurve::EaseIn, MUSIC_FADE_IN, MUSIC_FADE_OUT);
+            fade.fade_in();
+
+            // Wait for the track to load, then play a copy of it so its acoustics follow this player alone.
+            let track = &playlist.tracks[playback.track()];
+            let (stream, song) = match &track.sound {
+                TrackSound::Streamed(sound) => (sounds.streams.get(sound).map(Acoustic::instance), None),
+                TrackSound::Song(song) => (None, sounds.songs.get(song).map(Acoustic::instance)),
+            };
+            if stream.is_none() && song.is_none() {
+                continue;
+            }
+
+            let mut player = commands.spawn((
+                ChildOf(entity),
+                Track,
+                Transform::default(),
+                PlaybackSettings::ONCE
+                    .with_spatial(true)
+                    .with_volume(Volume::Linear(RUNNING_VOLUME))
+                    .paused(),
+                Bus::Music,
+                fade,
+                Attenuation::new(MUSIC_MIN_DISTANCE, MUSIC_MAX_DISTANCE, MUSIC_ROLLOFF),
+                Caption(format!("\"{}\" playing", track.title)),
+                Analysed,
+            ));
+            if let Some(stream) = stream {
+                player.insert(AudioPlayer(sounds.streams.add(stream)));
             }
+            if let Some(song) = song {
+                player.insert(AudioPlayer(sounds.songs.add(song)));
+            }
+            if let Some(lyrics) = &track.lyrics {
+                player.insert(Sung(lyrics.clone()));
+            }
+            playback.current = Some(player.id());
+        }
+    }
+}
+
+// Skip to the next track, crossfading it with the one that was playing.
+fn handle_skip(
+    mut commands: Commands,
+    mut events: MessageReader<InteractionEvent>,
+    mut stereos: Query<&mut Playback, With<Stereo>>,
+    mut tracks: Query<&mut Fade, With<Track>>,
+) {
+    for event in events.read() {
+        if event.id != INTERACTABLE_ID || event.verb != Verb::Skip {
+            continue;
+        }
+        let Ok(mut playback) = stereos.single_mut() else {
+            continue;
+        };
+        if playback.order.is_empty() {
+            continue;
+        }
+
+        // The next track starts fading in once the current one is cleared.
+        if let Some(current) = playback.current.take()
+            && let Ok(mut fade) = tracks.get_mut(current)
+        {
+            fade.fade_out();
+            commands.entity(current).insert(Ending).remove::<(Analysed, Sung)>();
+        }
+        playback.advance(true);
+    }
+}
+
+// Clear away skipped tracks once they've faded out.
+fn handle_ending(mut commands: Commands, tracks: Query<(Entity, &Fade), With<Ending>>) {
+    for (entity, fade) in &tracks {
+        if fade.is_silent() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// S toggles shuffling the playlist, R changes how it repeats. While a song plays, [ and ] slow it down or
+// speed it up, and I changes its instrument.
+fn handle_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    songs: Res<Assets<Song>>,
+    mut stereos: Query<&mut Playback, With<Stereo>>,
+    players: Query<&AudioPlayer<Song>, With<Track>>,
+) {
+    for mut playback in &mut stereos {
+        if playback.order.is_empty() {
+            continue;
+        }
+
+        if keyboard.just_pressed(KeyCode::KeyS) {
+            let shuffle = !playback.shuffle;
+            playback.set_shuffle(shuffle);
+        }
+        if keyboard.just_pressed(KeyCode::KeyR) {
+            playback.repeat = playback.repeat.next();
+        }
+
+        let Some(song) = playback
+            .current
+            .and_then(|current| players.get(current).ok())
+            .and_then(|player| songs.get(&player.0))
+        else {
+            continue;
+        };
+        let controls = song.controls();
+        if keyboard.just_pressed(KeyCode::BracketLeft) {
+            controls.set_tempo(controls.tempo() - TEMPO_STEP);
+        } else if keyboard.just_pressed(KeyCode::BracketRight) {
+            controls.set_tempo(controls.tempo() + TEMPO_STEP);
+        } else if keyboard.just_pressed(KeyCode::KeyI) {
+            controls.set_instrument(controls.instrument().next());
+        } else {
+            continue;
+        }
+        info!("Song: {:.0} bpm, {:?}", controls.tempo(), controls.instrument());
+    }
+}
+
+// Raise the equalizer bars with the music's spectrum.
+fn handle_equalizer(spectrum: Res<Spectrum>, mut bars: Query<(&EqualizerBar, &mut Sprite)>) {
+    if !spectrum.is_changed() {
+        return;
+    }
+
+    for (bar, mut sprite) in &mut bars {
+        sprite.custom_size = Some(Vec2::new(1.0, (spectrum.0[bar.0] * EQUALIZER_HEIGHT).round()));
+    }
+}
+
+// Show the title of the track playing, and how the playlist is playing.
+fn handle_caption(
+    playlists: Res<Assets<Playlist>>,
+    stereos: Query<(&State, &Playback), With<Stereo>>,
+    caption: Single<(&mut Text, &mut Visibility), With<NowPlaying>>,
+) {
+    let (mut text, mut visibility) = caption.into_inner();
+
+    let playing = stereos.iter().find_map(|(state, playback)| {
+        if *state != State::Running || playback.current.is_none() {
+            return None;
+        }
+        let playlist = playlists.get(&playback.playlist)?;
+
+        let mut modes = Vec::new();
+        if playback.shuffle {
+            modes.push("shuffle");
+        }
+        match playback.repeat {
+            Repeat::Off => {}
+            Repeat::One => modes.push("repeat one"),
+            Repeat::All => modes.push("repeat all"),
+        }
+
+        let title = &playlist.tracks[playback.track()].title;
+        Some(if modes.is_empty() {
+            format!("Now playing: {title}")
+        } else {
+            format!("Now playing: {title} ({})", modes.join(", "))
+        })
+    });
+
+    match playing {
+        Some(caption) => {
+            if text.0 != caption {
+                text.0 = caption;
+            }
+            visibility.set_if_neq(Visibility::Inherited);
+        }
+        None => {
+            visibility.set_if_neq(Visibility::Hidden);
         }
     }
 }
@@ -171,26 +526,70 @@ fn init(
     commands.insert_resource(sprite.clone());
 
     // Create the sprite starting in the off state.
+    commands
+        .spawn((
+            Sprite {
+                image: sprite.off_sprite,
+                texture_atlas: None,
+                ..default()
+            },
+            Transform::from_scale(Vec3::splat(SPRITE_SCALE)).with_translation(Vec3::new(90.0, -62.0, 5.0)),
+            Stereo,
+            AnimationConfig::new(0, 4, 4),
+            State::Off,
+            Playback {
+                playlist: asset_server.load("stereo/stereo.playlist"),
+                order: Vec::new(),
+                position: 0,
+                shuffle: false,
+                repeat: Repeat::All,
+                current: None,
+            },
+            Interactable {
+                id: INTERACTABLE_ID.to_string(),
+                height: SPRITE_HEIGHT * SPRITE_SCALE,
+                width: SPRITE_WIDTH * SPRITE_SCALE,
+                first: true,
+                stand_offset: STAND_OFFSET,
+                facing: Facing::Right,
+            },
+            Obstacle {
+                width: SPRITE_WIDTH * SPRITE_SCALE,
+                depth: OBSTACLE_DEPTH,
+            },
+        ))
+        .with_children(|parent| {
+            // Add the equalizer over the stereo's panel, flat until there's music.
+            for band in 0..BAND_COUNT {
+                parent.spawn((
+                    EqualizerBar(band),
+                    Sprite {
+                        color: EQUALIZER_COLOR,
+                        custom_size: Some(Vec2::new(1.0, 0.0)),
+                        ..default()
+                    },
+                    Anchor::BOTTOM_CENTER,
+                    Transform::from_translation(Vec3::new(
+                        EQUALIZER_LEFT + band as f32 * EQUALIZER_SPACING,
+                        EQUALIZER_BOTTOM,
+                        0.1,
+                    )),
+                ));
+            }
+        });
+
+    // Create the now playing caption, shown while the stereo is on.
     commands.spawn((
-        Sprite {
-            image: sprite.off_sprite,
-            texture_atlas: None,
+        NowPlaying,
+        Text::default(),
+        TextFont::from_font_size(CAPTION_SIZE),
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(8),
+            right: px(12),
             ..default()
         },
-        Transform::from_scale(Vec3::splat(SPRITE_SCALE)).with_translation(Vec3::new(90.0, -62.0, 5.0)),
-        Stereo,
-        AnimationConfig::new(0, 4, 4),
-        State::Off,
-        AudioPlayer::new(asset_server.load("stereo/merry_little_christmas.ogg")),
-        PlaybackSettings::LOOP
-            .with_spatial(true)
-            .with_volume(Volume::Linear(RUNNING_VOLUME))
-            .paused(),
-        Interactable {
-            id: INTERACTABLE_ID.to_string(),
-            height: SPRITE_HEIGHT * SPRITE_SCALE,
-            width: SPRITE_WIDTH * SPRITE_SCALE,
-            first: true,
-        },
+        Visibility::Hidden,
     ));
 }
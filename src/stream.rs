@@ -0,0 +1,150 @@
+use bevy::{
+    asset::{
+        AssetLoader, LoadContext,
+        io::{AssetSourceId, Reader, file::FileAssetReader},
+    },
+    audio::{AddAudioSource, Decodable, Source},
+    prelude::*,
+};
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use crate::{
+    acoustics::{Acoustic, AcousticControls, Acoustics},
+    analysis::{Tap, Tapped},
+};
+
+// A long audio file decoded a little at a time straight from disk while it plays, rather than held
+// in memory like an `AudioSource`. Load one with `asset_server.load::<StreamedAudio>(path)`, which picks
+// this loader over the usual audio loader for the same file.
+#[derive(Asset, TypePath)]
+pub struct StreamedAudio {
+    path: PathBuf,
+    // What's been played, for analysing the audio.
+    tap: Tap,
+    acoustics: AcousticControls,
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(std::io::Error),
+    Decode(rodio::decoder::DecoderError),
+    // Only files in the app's asset folder can be read as they play.
+    Source,
+}
+
+struct StreamedAudioLoader {
+    // The asset folder, as the app's asset plugin was set up to read it.
+    root: PathBuf,
+}
+
+// Add the streamed audio systems.
+pub fn add_systems(app: &mut App) {
+    let folder = app
+        .get_added_plugins::<AssetPlugin>()
+        .first()
+        .map(|plugin| plugin.file_path.clone())
+        .unwrap_or_else(|| AssetPlugin::default().file_path);
+    let root = FileAssetReader::new(folder).root_path().clone();
+
+    app.add_audio_source::<StreamedAudio>()
+        .register_asset_loader(StreamedAudioLoader { root });
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(error) => write!(f, "could not open streamed audio: {error}"),
+            StreamError::Decode(error) => write!(f, "could not decode streamed audio: {error}"),
+            StreamError::Source => write!(f, "streamed audio has to come from the asset folder"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<std::io::Error> for StreamError {
+    fn from(error: std::io::Error) -> Self {
+        StreamError::Io(error)
+    }
+}
+
+impl From<rodio::decoder::DecoderError> for StreamError {
+    fn from(error: rodio::decoder::DecoderError) -> Self {
+        StreamError::Decode(error)
+    }
+}
+
+impl AssetLoader for StreamedAudioLoader {
+    type Asset = StreamedAudio;
+    type Settings = ();
+    type Error = StreamError;
+
+    // Only check the file can be decoded, the audio itself is read as it plays.
+    async fn load(
+        &self,
+        _reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<StreamedAudio, StreamError> {
+        if load_context.asset_path().source() != &AssetSourceId::Default {
+            return Err(StreamError::Source);
+        }
+        let path = self.root.join(load_context.path());
+        rodio::Decoder::new(BufReader::new(File::open(&path)?))?;
+
+        Ok(StreamedAudio {
+            path,
+            tap: Tap::default(),
+            acoustics: AcousticControls::default(),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ogg", "wav"]
+    }
+}
+
+impl StreamedAudio {
+    pub fn tap(&self) -> &Tap {
+        &self.tap
+    }
+}
+
+impl Decodable for StreamedAudio {
+    type DecoderItem = f32;
+    type Decoder = Acoustics<Tapped<Box<dyn Source<Item = i16> + Send>>>;
+
+    // The file is opened again each time it plays, and if it's gone or broken since it loaded, the track
+    // plays nothing and ends rather than taking down the audio thread.
+    fn decoder(&self) -> Self::Decoder {
+        let source: Box<dyn Source<Item = i16> + Send> = match File::open(&self.path)
+            .map_err(StreamError::from)
+            .and_then(|file| Ok(rodio::Decoder::new(BufReader::new(file))?))
+        {
+            Ok(decoder) => Box::new(decoder),
+            Err(error) => {
+                error!("{}: {error}", self.path.display());
+                Box::new(rodio::source::Empty::new())
+            }
+        };
+        Acoustics::new(Tapped::new(source, self.tap.clone()), self.acoustics.clone())
+    }
+}
+
+impl Acoustic for StreamedAudio {
+    fn acoustics(&self) -> &AcousticControls {
+        &self.acoustics
+    }
+
+    // Copies are analysed apart too, so each tap only hears its own player.
+    fn instance(&self) -> Self {
+        StreamedAudio {
+            path: self.path.clone(),
+            tap: Tap::default(),
+            acoustics: AcousticControls::default(),
+        }
+    }
+}
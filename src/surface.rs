@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+
+use crate::navigation::{FLOOR_MAX, FLOOR_MIN};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Surface {
+    Wood,
+    Rug,
+    Snow,
+    Tile,
+}
+
+// Add to entities marking an area of floor with a surface type.
+#[derive(Component)]
+pub struct SurfaceRegion {
+    pub surface: Surface,
+    pub width: f32,
+    pub height: f32,
+}
+
+const RUG_WIDTH: f32 = 64.0;
+const RUG_HEIGHT: f32 = 5.0;
+const RUG_OFFSET: f32 = -20.0;
+
+const TILE_WIDTH: f32 = 32.0;
+const TILE_HEIGHT: f32 = 4.0;
+const TILE_OFFSET: f32 = -20.0;
+
+pub const WALL_LEFT: f32 = -172.0;
+pub const WALL_RIGHT: f32 = 184.0;
+
+impl Surface {
+    // How quickly feet grip the surface, scaling acceleration and deceleration.
+    pub fn friction(self) -> f32 {
+        match self {
+            Surface::Wood => 1.0,
+            Surface::Rug => 1.6,
+            Surface::Tile => 0.8,
+            // Packed snow outside is icy.
+            Surface::Snow => 0.25,
+        }
+    }
+
+    // Name used for the surface's sound banks.
+    pub fn name(self) -> &'static str {
+        match self {
+            Surface::Wood => "wood",
+            Surface::Rug => "rug",
+            Surface::Snow => "snow",
+            Surface::Tile => "tile",
+        }
+    }
+}
+
+// Add the surface systems.
+pub fn add_systems(app: &mut App) {
+    app.add_systems(Startup, init);
+}
+
+// Find the surface under a floor position, the house floor is wood unless marked otherwise.
+pub fn surface_at<'a>(
+    position: Vec2,
+    mut regions: impl Iterator<Item = (&'a Transform, &'a SurfaceRegion)>,
+) -> Surface {
+    regions
+        .find(|(transform, region)| {
+            Rect::from_center_size(transform.translation.truncate(), Vec2::new(region.width, region.height))
+                .contains(position)
+        })
+        .map_or(Surface::Wood, |(_, region)| region.surface)
+}
+
+// Surface initialization.
+fn init(mut commands: Commands) {
+    let floor_height = FLOOR_MAX.y - FLOOR_MIN.y;
+    let floor_y = (FLOOR_MAX.y + FLOOR_MIN.y) / 2.0;
+
+    // Create the rug in front of the fireplace.
+    commands.spawn((
+        Transform::from_translation(Vec3::new(0.0, floor_y, 3.0)),
+        Visibility::default(),
+        SurfaceRegion {
+            surface: Surface::Rug,
+            width: RUG_WIDTH,
+            height: floor_height,
+        },
+        children![(
+            Sprite {
+                color: Color::srgb(0.55, 0.12, 0.14),
+                custom_size: Some(Vec2::new(RUG_WIDTH, RUG_HEIGHT)),
+                ..default()
+            },
+            Transform::from_translation(Vec3::new(0.0, RUG_OFFSET, 0.0)),
+        )],
+    ));
+
+    // Create the tiled entryway inside the right wall.
+    commands.spawn((
+        Transform::from_translation(Vec3::new(WALL_RIGHT - TILE_WIDTH / 2.0, floor_y, 3.0)),
+        Visibility::default(),
+        SurfaceRegion {
+            surface: Surface::Tile,
+            width: TILE_WIDTH,
+            height: floor_height,
+        },
+        children![(
+            Sprite {
+                color: Color::srgb(0.62, 0.66, 0.7),
+                custom_size: Some(Vec2::new(TILE_WIDTH, TILE_HEIGHT)),
+                ..default()
+            },
+            Transform::from_translation(Vec3::new(0.0, TILE_OFFSET, 0.0)),
+        )],
+    ));
+
+    // Create the snow on either side of the house.
+    for (left, right) in [(FLOOR_MIN.x, WALL_LEFT), (WALL_RIGHT, FLOOR_MAX.x)] {
+        commands.spawn((
+            Transform::from_translation(Vec3::new((left + right) / 2.0, floor_y, 0.0)),
+            SurfaceRegion {
+                surface: Surface::Snow,
+                width: right - left,
+                height: floor_height,
+            },
+        ));
+    }
+}
@@ -0,0 +1,433 @@
+use bevy::{
+    asset::LoadContext,
+    audio::{AddAudioSource, Decodable, Source},
+    prelude::*,
+};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::{
+    acoustics::{Acoustic, AcousticControls, Acoustics},
+    analysis::{Tap, Tapped},
+    text_asset::{TextAsset, TextAssetError, TextAssetLoader},
+};
+
+// A tune played by the built-in synthesizer, loaded from a tiny `.song` file.
+//
+// `tempo: bpm` and `instrument: square|triangle|saw|sine` set how it plays to begin with, and both can
+// be changed while it plays. Every other line is `voice: notes`, with the voices playing together and
+// lines for the same voice carrying on from each other. Notes are a name and octave with an optional
+// length in beats, like `C#4/0.5`, and `-` is a rest (`-/2`).
+#[derive(Asset, TypePath)]
+pub struct Song {
+    voices: Arc<Vec<Vec<Note>>>,
+    controls: SongControls,
+    // What's been played, for analysing the audio.
+    tap: Tap,
+    acoustics: AcousticControls,
+}
+
+#[derive(Clone, Copy)]
+struct Note {
+    // Frequency in hertz, or none for a rest.
+    frequency: Option<f32>,
+    beats: f32,
+}
+
+// The shape of the wave the synthesizer plays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instrument {
+    Square,
+    Triangle,
+    Saw,
+    Sine,
+}
+
+// Settings shared with a song while it plays, so changes are heard straight away.
+#[derive(Clone)]
+pub struct SongControls {
+    // Beats per minute, as the bits of an f32.
+    tempo: Arc<AtomicU32>,
+    instrument: Arc<AtomicU8>,
+}
+
+// Plays the voices of a song, a sample at a time.
+pub struct Synth {
+    voices: Arc<Vec<Vec<Note>>>,
+    controls: SongControls,
+    // Each voice's note and how far through it it is, in beats.
+    notes: Vec<usize>,
+    elapsed: Vec<f32>,
+    phases: Vec<f32>,
+}
+
+const SAMPLE_RATE: u32 = 22_050;
+// Keeps several voices together from clipping.
+const VOICE_VOLUME: f32 = 0.2;
+// Seconds for a note to start and stop, so notes don't click.
+const ATTACK: f32 = 0.005;
+const RELEASE: f32 = 0.03;
+// How far a held note dies away to, and how quickly.
+const SUSTAIN: f32 = 0.6;
+const DECAY: f32 = 0.15;
+
+const MIN_TEMPO: f32 = 40.0;
+const MAX_TEMPO: f32 = 300.0;
+
+// Add the synthesizer systems.
+pub fn add_systems(app: &mut App) {
+    app.add_audio_source::<Song>()
+        .init_asset_loader::<TextAssetLoader<Song>>();
+}
+
+impl Instrument {
+    // The instrument after this one, for cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            Instrument::Square => Instrument::Triangle,
+            Instrument::Triangle => Instrument::Saw,
+            Instrument::Saw => Instrument::Sine,
+            Instrument::Sine => Instrument::Square,
+        }
+    }
+
+    fn from_index(index: u8) -> Self {
+        match index {
+            1 => Instrument::Triangle,
+            2 => Instrument::Saw,
+            3 => Instrument::Sine,
+            _ => Instrument::Square,
+        }
+    }
+
+    fn index(self) -> u8 {
+        match self {
+            Instrument::Square => 0,
+            Instrument::Triangle => 1,
+            Instrument::Saw => 2,
+            Instrument::Sine => 3,
+        }
+    }
+
+    // The wave's level part way through its cycle, with the phase from 0 to 1.
+    fn wave(self, phase: f32) -> f32 {
+        match self {
+            Instrument::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Instrument::Triangle => 4.0 * (phase - 0.5).abs() - 1.0,
+            Instrument::Saw => 2.0 * phase - 1.0,
+            Instrument::Sine => (phase * TAU).sin(),
+        }
+    }
+}
+
+impl SongControls {
+    fn new(tempo: f32, instrument: Instrument) -> Self {
+        SongControls {
+            tempo: Arc::new(AtomicU32::new(tempo.to_bits())),
+            instrument: Arc::new(AtomicU8::new(instrument.index())),
+        }
+    }
+
+    pub fn tempo(&self) -> f32 {
+        f32::from_bits(self.tempo.load(Ordering::Relaxed))
+    }
+
+    pub fn set_tempo(&self, tempo: f32) {
+        let tempo = tempo.clamp(MIN_TEMPO, MAX_TEMPO);
+        self.tempo.store(tempo.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn instrument(&self) -> Instrument {
+        Instrument::from_index(self.instrument.load(Ordering::Relaxed))
+    }
+
+    pub fn set_instrument(&self, instrument: Instrument) {
+        self.instrument.store(instrument.index(), Ordering::Relaxed);
+    }
+}
+
+impl TextAsset for Song {
+    const EXTENSIONS: &'static [&'static str] = &["song"];
+
+    fn from_text(source: &str, _load_context: &mut LoadContext<'_>) -> Result<Self, TextAssetError> {
+        Song::parse(source)
+    }
+}
+
+impl Song {
+    fn parse(source: &str) -> Result<Self, TextAssetError> {
+        let mut tempo = 120.0;
+        let mut instrument = Instrument::Square;
+        let mut names: Vec<String> = Vec::new();
+        let mut voices: Vec<Vec<Note>> = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: &str| TextAssetError::at(index + 1, message);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| error("expected 'voice: notes' or a setting"))?;
+            match (key.trim(), value.trim()) {
+                ("tempo", value) => {
+                    tempo = value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|tempo| (MIN_TEMPO..=MAX_TEMPO).contains(tempo))
+                        .ok_or_else(|| error("tempo must be between 40 and 300 beats per minute"))?;
+                }
+                ("instrument", "square") => instrument = Instrument::Square,
+                ("instrument", "triangle") => instrument = Instrument::Triangle,
+                ("instrument", "saw") => instrument = Instrument::Saw,
+                ("instrument", "sine") => instrument = Instrument::Sine,
+                ("instrument", _) => return Err(error("unknown instrument")),
+                (name, notes) => {
+                    let notes = notes
+                        .split_whitespace()
+                        .map(|note| parse_note(note).ok_or_else(|| error(&format!("invalid note '{note}'"))))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    match names.iter().position(|existing| existing == name) {
+                        Some(voice) => voices[voice].extend(notes),
+                        None => {
+                            names.push(name.to_string());
+                            voices.push(notes);
+                        }
+                    }
+                }
+            }
+        }
+
+        if voices.iter().all(Vec::is_empty) {
+            return Err(TextAssetError::at(source.lines().count(), "song has no notes"));
+        }
+        Ok(Song {
+            voices: Arc::new(voices),
+            controls: SongControls::new(tempo, instrument),
+            tap: Tap::default(),
+            acoustics: AcousticControls::default(),
+        })
+    }
+
+    pub fn controls(&self) -> &SongControls {
+        &self.controls
+    }
+
+    pub fn tap(&self) -> &Tap {
+        &self.tap
+    }
+}
+
+// Read a note like `C#4/0.5`, or a rest like `-/2`.
+fn parse_note(source: &str) -> Option<Note> {
+    let (pitch, beats) = match source.split_once('/') {
+        Some((pitch, beats)) => (pitch, beats.parse().ok().filter(|beats: &f32| *beats > 0.0)?),
+        None => (source, 1.0),
+    };
+    if pitch == "-" {
+        return Some(Note { frequency: None, beats });
+    }
+
+    let mut chars = pitch.chars();
+    let mut semitone: i32 = match chars.next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let mut octave = chars.as_str();
+    if let Some(rest) = octave.strip_prefix('#') {
+        semitone += 1;
+        octave = rest;
+    } else if let Some(rest) = octave.strip_prefix('b') {
+        semitone -= 1;
+        octave = rest;
+    }
+    let octave: i32 = octave.parse().ok()?;
+
+    // Counting up from A4 at 440 hertz.
+    let midi = (octave + 1) * 12 + semitone;
+    Some(Note {
+        frequency: Some(440.0 * 2f32.powf((midi - 69) as f32 / 12.0)),
+        beats,
+    })
+}
+
+impl Decodable for Song {
+    type DecoderItem = f32;
+    type Decoder = Acoustics<Tapped<Synth>>;
+
+    fn decoder(&self) -> Self::Decoder {
+        let count = self.voices.len();
+        let synth = Synth {
+            voices: self.voices.clone(),
+            controls: self.controls.clone(),
+            notes: vec![0; count],
+            elapsed: vec![0.0; count],
+            phases: vec![0.0; count],
+        };
+        Acoustics::new(Tapped::new(synth, self.tap.clone()), self.acoustics.clone())
+    }
+}
+
+impl Acoustic for Song {
+    fn acoustics(&self) -> &AcousticControls {
+        &self.acoustics
+    }
+
+    // Copies share the tempo and instrument, but are analysed apart so each tap only hears its own player.
+    fn instance(&self) -> Self {
+        Song {
+            voices: self.voices.clone(),
+            controls: self.controls.clone(),
+            tap: Tap::default(),
+            acoustics: AcousticControls::default(),
+        }
+    }
+}
+
+impl Iterator for Synth {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let beat_length = 60.0 / self.controls.tempo();
+        let instrument = self.controls.instrument();
+        let step = 1.0 / (SAMPLE_RATE as f32 * beat_length);
+
+        let mut sample = 0.0;
+        let mut playing = false;
+        for (voice, notes) in self.voices.iter().enumerate() {
+            let Some(note) = notes.get(self.notes[voice]) else {
+                continue;
+            };
+            playing = true;
+
+            if let Some(frequency) = note.frequency {
+                // Shape each note so it starts and stops cleanly, fading a little as it's held.
+                let time = self.elapsed[voice] * beat_length;
+                let remaining = (note.beats - self.elapsed[voice]) * beat_length;
+                let envelope = (time / ATTACK).min(1.0)
+                    * (remaining / RELEASE).min(1.0)
+                    * (SUSTAIN + (1.0 - SUSTAIN) * (-time / DECAY).exp());
+                sample += instrument.wave(self.phases[voice]) * envelope * VOICE_VOLUME;
+
+                self.phases[voice] = (self.phases[voice] + frequency / SAMPLE_RATE as f32).fract();
+            }
+
+            self.elapsed[voice] += step;
+            if self.elapsed[voice] >= note.beats {
+                self.elapsed[voice] -= note.beats;
+                self.notes[voice] += 1;
+                self.phases[voice] = 0.0;
+            }
+        }
+
+        playing.then_some(sample)
+    }
+}
+
+impl Source for Synth {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    // The length changes with the tempo.
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frequency(source: &str) -> f32 {
+        parse_note(source).and_then(|note| note.frequency).unwrap()
+    }
+
+    fn error_line(source: &str) -> usize {
+        match Song::parse(source) {
+            Err(TextAssetError::Parse { line, .. }) => line,
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn parses_pitches() {
+        assert_eq!(frequency("A4"), 440.0);
+        assert_eq!(frequency("A5"), 880.0);
+        assert_eq!(frequency("A3"), 220.0);
+        assert!((frequency("C4") - 261.63).abs() < 0.01);
+        assert_eq!(frequency("C#4"), frequency("Db4"));
+        assert!((frequency("A#4") - 466.16).abs() < 0.01);
+        assert_eq!(frequency("Cb5"), frequency("B4"));
+    }
+
+    #[test]
+    fn parses_lengths_and_rests() {
+        assert_eq!(parse_note("E4").unwrap().beats, 1.0);
+        assert_eq!(parse_note("E4/0.5").unwrap().beats, 0.5);
+
+        let rest = parse_note("-/2").unwrap();
+        assert_eq!(rest.frequency, None);
+        assert_eq!(rest.beats, 2.0);
+        assert_eq!(parse_note("-").unwrap().beats, 1.0);
+    }
+
+    #[test]
+    fn rejects_invalid_notes() {
+        for source in [
+            "", "H4", "C", "c4", "C#", "Cx4", "C4/", "C4/0", "C4/-1", "C4/long", "-/0",
+        ] {
+            assert!(parse_note(source).is_none(), "{source:?}");
+        }
+    }
+
+    #[test]
+    fn parses_songs() {
+        let song = Song::parse("# Tune\ntempo: 90\ninstrument: saw\nlead: C4 D4/2\nbass: C2/3\nlead: - E4\n").unwrap();
+        assert_eq!(song.controls.tempo(), 90.0);
+        assert_eq!(song.controls.instrument(), Instrument::Saw);
+
+        let beats: Vec<Vec<f32>> = song
+            .voices
+            .iter()
+            .map(|voice| voice.iter().map(|note| note.beats).collect())
+            .collect();
+        assert_eq!(beats, [vec![1.0, 2.0, 1.0, 1.0], vec![3.0]]);
+        assert_eq!(song.voices[0][2].frequency, None);
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        assert_eq!(error_line("lead: C4\nlead C4\n"), 2);
+        assert_eq!(error_line("lead: C4 X4\n"), 1);
+        assert_eq!(error_line("tempo: 1000\nlead: C4\n"), 1);
+        assert_eq!(error_line("\ninstrument: kazoo\n"), 2);
+        assert_eq!(error_line("# Nothing to play\ntempo: 100\n"), 2);
+    }
+}
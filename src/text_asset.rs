@@ -0,0 +1,79 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use std::fmt;
+use std::marker::PhantomData;
+
+// Assets written as plain text files, like dialogue, sound banks and playlists.
+pub trait TextAsset: Asset + Sized {
+    // The file extensions the asset is loaded from.
+    const EXTENSIONS: &'static [&'static str];
+
+    // Build the asset from the file's text, loading any files it refers to through the load context.
+    fn from_text(source: &str, load_context: &mut LoadContext<'_>) -> Result<Self, TextAssetError>;
+}
+
+#[derive(Debug)]
+pub enum TextAssetError {
+    Io(std::io::Error),
+    // Lines count from 1, with 0 for problems with the file as a whole.
+    Parse { line: usize, message: String },
+}
+
+// Loads any text asset from its file.
+pub struct TextAssetLoader<T>(PhantomData<T>);
+
+impl TextAssetError {
+    // A problem with a line of the file.
+    pub fn at(line: usize, message: impl Into<String>) -> Self {
+        TextAssetError::Parse {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for TextAssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextAssetError::Io(error) => write!(f, "could not read file: {error}"),
+            TextAssetError::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for TextAssetError {}
+
+impl From<std::io::Error> for TextAssetError {
+    fn from(error: std::io::Error) -> Self {
+        TextAssetError::Io(error)
+    }
+}
+
+impl<T> Default for TextAssetLoader<T> {
+    fn default() -> Self {
+        TextAssetLoader(PhantomData)
+    }
+}
+
+impl<T: TextAsset> AssetLoader for TextAssetLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = TextAssetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<T, TextAssetError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        T::from_text(&String::from_utf8_lossy(&bytes), load_context)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        T::EXTENSIONS
+    }
+}
@@ -1,9 +1,17 @@
-use bevy::{audio::Volume, prelude::*};
+use bevy::{prelude::*, window::PrimaryWindow};
 use rand::{Rng, rng};
+use std::collections::VecDeque;
 use std::time::Duration;
 
-use crate::animation::AnimationConfig;
-use crate::interaction::{InRange, InteractionEvent, Interactor};
+use crate::animation::{AnimationConfig, CharacterSprites, WALKING_LAST_FRAME};
+use crate::bubble::{BubbleContent, BubbleRequest, BubbleStyle};
+use crate::dialogue;
+use crate::fireplace::{self, Fireplace};
+use crate::interaction::{self, Facing, InRange, Interactable, InteractionEvent, Interactor, Verb};
+use crate::navigation::{FLOOR_MAX, FLOOR_MIN, NavGraph};
+use crate::soundbank::PlaySound;
+use crate::surface::{self, SurfaceRegion};
+use crate::tree::{self, Tree};
 
 #[derive(Component, Clone, Copy, PartialEq)]
 enum State {
@@ -30,54 +38,148 @@ struct Trigger {
     direction: Direction,
 }
 
+// Added to the man while he walks to an interactable's stand position.
+#[derive(Component)]
+struct Approach {
+    id: String,
+    verb: Verb,
+    facing: Direction,
+    // Where the man is headed, followed if the interactable moves.
+    stand: Vec2,
+}
+
+// Waypoints the man is walking through, removed once he arrives.
+#[derive(Component)]
+struct Route(VecDeque<Vec2>);
+
+// Added to the man while the run modifier is held.
+#[derive(Component)]
+struct Run;
+
+#[derive(Component, Default)]
+struct Velocity(Vec2);
+
+#[derive(Clone, Copy, PartialEq)]
+enum IdleBehaviour {
+    LookAround,
+    Stretch,
+    Yawn,
+    GlanceAtTree,
+}
+
+// Added to the man while an idle behaviour is playing.
+#[derive(Component)]
+struct IdleAnimation {
+    behaviour: IdleBehaviour,
+    timer: Timer,
+}
+
 #[derive(Component)]
 struct IdleTimer(Timer);
 
 #[derive(Component)]
 struct StepTimer(Timer);
 
-#[derive(Clone, Resource)]
-struct AudioAssets {
-    left_steps: Vec<Handle<AudioSource>>,
-    right_steps: Vec<Handle<AudioSource>>,
-}
+#[derive(Component)]
+struct ColdTimer(Timer);
 
 #[derive(Clone, Resource)]
 struct SpriteAssets {
-    walking_sprite: Handle<Image>,
-    walking_layout: Handle<TextureAtlasLayout>,
-    standing_sprite: Handle<Image>,
-    standing_layout: Handle<TextureAtlasLayout>,
+    idle_sprite: Handle<Image>,
+    idle_layout: Handle<TextureAtlasLayout>,
 }
 
 #[derive(Component)]
-struct TheMan;
+pub struct TheMan;
 
 const SPRITE_SCALE: f32 = 1.5;
 
 const WALKING_SPEED: f32 = 30.0;
-const WALKING_VOLUME: f32 = 0.85;
+const WALKING_FPS: u8 = 10;
+const RUNNING_SPEED: f32 = 55.0;
+const RUNNING_FPS: u8 = 16;
+
+const ACCELERATION: f32 = 120.0;
+const DECELERATION: f32 = 160.0;
+const ARRIVE_GAIN: f32 = 3.0;
+const ARRIVE_DISTANCE: f32 = 1.0;
 
 const WALKING_TIMER: f32 = 0.45;
 const WALKING_TIMER_DELAY: f32 = 0.225;
+const RUNNING_TIMER: f32 = 0.28;
 
 const AUDIO_WIDTH: f32 = -8.;
 
+const CLICK_HEIGHT: f32 = 24.0;
+// How far an interactable's stand position can move before the man changes course.
+const RETARGET_DISTANCE: f32 = 2.0;
+
+const IDLE_DELAY_MIN: f32 = 3.0;
+const IDLE_DELAY_MAX: f32 = 8.0;
+const IDLE_NEARBY_DISTANCE: f32 = 80.0;
+const IDLE_FPS: u8 = 4;
+const GLANCE_DURATION: f32 = 2.5;
+
+const COLD_INTERVAL: f32 = 6.0;
+const COLD_DURATION: f32 = 2.5;
+const NO_PATH_DURATION: f32 = 1.5;
+
 // Add the animation systems.
 pub fn add_systems(app: &mut App) {
     app.add_message::<Trigger>()
         .add_systems(Startup, init)
-        .add_systems(Update, (handle_animations, idle_action))
-        .add_systems(Update, (handle_keys, trigger_animation))
-        .add_systems(Update, handle_movement)
-        .add_systems(Update, handle_audio);
+        .add_systems(Update, (handle_animations, idle_action, handle_idle_animation))
+        .add_systems(
+            Update,
+            (
+                (handle_keys, handle_click).run_if(not(dialogue::is_open)),
+                handle_held_keys,
+                trigger_animation,
+            ),
+        )
+        .add_systems(
+            Update,
+            (handle_approach_target, handle_movement, handle_approach).chain(),
+        )
+        .add_systems(Update, (handle_audio, handle_cold));
+}
+
+impl IdleBehaviour {
+    // The behaviour's frames in the idle sprite sheet, if it has any.
+    fn frames(self) -> Option<(usize, usize)> {
+        match self {
+            IdleBehaviour::Stretch => Some((0, 5)),
+            IdleBehaviour::Yawn => Some((6, 11)),
+            IdleBehaviour::LookAround => Some((12, 17)),
+            IdleBehaviour::GlanceAtTree => None,
+        }
+    }
 }
 
 // Loop through all the man's sprites and advance their animation.
-fn handle_animations(time: Res<Time>, mut query: Query<(&mut AnimationConfig, &mut Sprite, &mut State), With<TheMan>>) {
-    for (mut config, mut sprite, mut state) in &mut query {
-        // Idle state only has one frame so skip.
-        if *state == State::Idle {
+fn handle_animations(
+    time: Res<Time>,
+    mut query: Query<
+        (
+            &mut AnimationConfig,
+            &mut Sprite,
+            &mut State,
+            Option<&IdleAnimation>,
+            Has<Run>,
+        ),
+        With<TheMan>,
+    >,
+) {
+    for (mut config, mut sprite, mut state, idle_animation, running) in &mut query {
+        let idling = idle_animation.is_some_and(|animation| animation.behaviour.frames().is_some());
+
+        // Running speeds up the walk cycle.
+        if !idling {
+            config.fps = if running { RUNNING_FPS } else { WALKING_FPS };
+        }
+
+        // Idle state only has one frame so skip, unless an idle behaviour is playing.
+        if *state == State::Idle && !idling {
             continue;
         }
 
@@ -91,56 +193,109 @@ fn handle_animations(time: Res<Time>, mut query: Query<(&mut AnimationConfig, &m
             }
         }
 
-        // Track how long the current sprite has been displayed.
-        config.frame_timer.tick(time.delta());
-
-        if config.frame_timer.just_finished()
-            && let Some(atlas) = &mut sprite.texture_atlas
-        {
-            // On last frame, reset to the first, otherwise advance.
-            if atlas.index == config.last_index {
-                atlas.index = config.first_index;
-            } else {
-                atlas.index += 1;
-            }
-            config.frame_timer = AnimationConfig::timer_from_fps(config.fps);
+        // Idle behaviours play once, everything else loops.
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            config.advance(time.delta(), atlas, !idling);
         }
     }
 }
 
 // Handle key input and send animation events.
 fn handle_keys(
+    mut commands: Commands,
     keyboard: Res<ButtonInput<KeyCode>>,
+    graph: Res<NavGraph>,
     mut trigger_events: MessageWriter<Trigger>,
-    mut interaction_events: MessageWriter<InteractionEvent>,
-    query: Query<&InRange, With<TheMan>>,
+    mut bubble_events: MessageWriter<BubbleRequest>,
+    query: Single<(Entity, &Transform, &Direction, Option<&InRange>), With<TheMan>>,
+    interactables: Query<(&Transform, &Interactable)>,
 ) {
+    let (entity, transform, direction, in_range) = query.into_inner();
+
     // Check for key presses.
     if keyboard.just_pressed(KeyCode::ArrowLeft) {
+        commands.entity(entity).remove::<(Route, Approach)>();
         trigger_events.write(Trigger {
             state: State::Walking,
             direction: Direction::Left,
         });
     } else if keyboard.just_pressed(KeyCode::ArrowRight) {
+        commands.entity(entity).remove::<(Route, Approach)>();
         trigger_events.write(Trigger {
             state: State::Walking,
             direction: Direction::Right,
         });
-    } else if keyboard.just_pressed(KeyCode::ArrowUp) {
-        trigger_events.write(Trigger {
-            state: State::Action,
-            direction: Direction::Right,
+    } else if keyboard.any_just_pressed([KeyCode::ArrowUp, KeyCode::ArrowDown, KeyCode::KeyN, KeyCode::KeyP]) {
+        // Up uses an interactable, down examines it, N skips to its next thing and P pokes it.
+        let verb = if keyboard.just_pressed(KeyCode::ArrowUp) {
+            Verb::Use
+        } else if keyboard.just_pressed(KeyCode::ArrowDown) {
+            Verb::Examine
+        } else if keyboard.just_pressed(KeyCode::KeyN) {
+            Verb::Skip
+        } else {
+            Verb::Poke
+        };
+
+        // If in range of an interactable, walk to its stand position before interacting.
+        let target = in_range.and_then(|in_range| {
+            interactables
+                .iter()
+                .find(|(_, interactable)| interactable.id == in_range.id)
         });
 
-        // If in range of an interactable, send interaction event
-        if let Ok(in_range) = query.single() {
-            interaction_events.write(InteractionEvent {
-                id: in_range.id.clone(),
+        if let Some((interactable_transform, interactable)) = target {
+            let position = transform.translation.truncate();
+            let stand = interaction::stand_position(&graph, interactable_transform, interactable, position.y);
+            let facing = match interactable.facing {
+                Facing::Left => Direction::Left,
+                Facing::Right => Direction::Right,
+            };
+
+            let Some(route) = graph.find_path(position, stand) else {
+                no_path(&mut bubble_events, entity);
+                return;
+            };
+            commands.entity(entity).insert((
+                Route(route),
+                Approach {
+                    id: interactable.id.clone(),
+                    verb,
+                    facing,
+                    stand,
+                },
+            ));
+        } else if verb == Verb::Use {
+            trigger_events.write(Trigger {
+                state: State::Action,
+                direction: *direction,
             });
         }
     }
+}
+
+// Handle keys that are held down, even during dialogue so letting go of them then isn't missed.
+fn handle_held_keys(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut trigger_events: MessageWriter<Trigger>,
+    query: Single<(Entity, Has<Route>, Has<Run>), With<TheMan>>,
+) {
+    let (entity, routing, running) = query.into_inner();
+
+    // Holding shift runs.
+    let run = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if run && !running {
+        commands.entity(entity).insert(Run);
+    } else if !run && running {
+        commands.entity(entity).remove::<Run>();
+    }
+
+    // Check for key releases, a route keeps walking until it arrives.
+    if routing {
+        return;
+    }
 
-    // Check for key releases.
     if keyboard.just_released(KeyCode::ArrowLeft) && !keyboard.pressed(KeyCode::ArrowRight) {
         trigger_events.write(Trigger {
             state: State::Idle,
@@ -156,59 +311,256 @@ fn handle_keys(
     }
 }
 
-// Move the man based on the current state.
-fn handle_movement(time: Res<Time>, mut sprite_position: Query<(&State, &Direction, &mut Transform), With<TheMan>>) {
-    for (state, direction, mut transform) in &mut sprite_position {
-        match *state {
-            State::Idle | State::Action => (),
-            State::Walking => match *direction {
-                Direction::Left => {
-                    transform.translation.x -= WALKING_SPEED * time.delta_secs();
+// Walk to the floor position under the cursor when clicked.
+fn handle_click(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    graph: Res<NavGraph>,
+    mut bubble_events: MessageWriter<BubbleRequest>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    query: Single<(Entity, &Transform), With<TheMan>>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let (camera, camera_transform) = camera.into_inner();
+    let Some(click) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    // Clicks on the floor are where the feet go, ignore anything above or below it.
+    let target = click + Vec2::new(0.0, CLICK_HEIGHT);
+    if target.y < FLOOR_MIN.y || target.y > FLOOR_MAX.y {
+        return;
+    }
+
+    let (entity, transform) = query.into_inner();
+    match graph.find_path(transform.translation.truncate(), target) {
+        Some(route) => {
+            commands.entity(entity).remove::<Approach>().insert(Route(route));
+        }
+        None => no_path(&mut bubble_events, entity),
+    }
+}
+
+// Stay put and say so when there's no way around the furniture to where the man was asked to go.
+fn no_path(bubble_events: &mut MessageWriter<BubbleRequest>, entity: Entity) {
+    bubble_events.write(BubbleRequest {
+        entity,
+        content: BubbleContent::Text("I can't get there.".to_string()),
+        style: BubbleStyle::Thought,
+        duration: NO_PATH_DURATION,
+    });
+}
+
+// Change course when an interactable being approached moves, like someone wandering about.
+fn handle_approach_target(
+    mut commands: Commands,
+    graph: Res<NavGraph>,
+    mut bubble_events: MessageWriter<BubbleRequest>,
+    mut query: Query<(Entity, &Transform, &mut Approach, &mut Route), With<TheMan>>,
+    interactables: Query<(&Transform, &Interactable), Without<TheMan>>,
+) {
+    for (entity, transform, mut approach, mut route) in &mut query {
+        let Some((interactable_transform, interactable)) = interactables
+            .iter()
+            .find(|(_, interactable)| interactable.id == approach.id)
+        else {
+            continue;
+        };
+
+        let stand = interaction::stand_position(&graph, interactable_transform, interactable, approach.stand.y);
+        if stand.distance(approach.stand) > RETARGET_DISTANCE {
+            let position = transform.translation.truncate();
+            match graph.find_path(position, stand) {
+                Some(path) => {
+                    route.0 = path;
+                    approach.stand = stand;
                 }
-                Direction::Right => {
-                    transform.translation.x += WALKING_SPEED * time.delta_secs();
+                // Give up when the target has moved somewhere out of reach, stopping where the man is.
+                None => {
+                    route.0.clear();
+                    commands.entity(entity).remove::<Approach>();
+                    no_path(&mut bubble_events, entity);
                 }
-            },
+            }
         }
     }
 }
 
-fn handle_audio(
+// Finish an approach once the man reaches the stand position, facing the interactable.
+fn handle_approach(
     mut commands: Commands,
+    mut trigger_events: MessageWriter<Trigger>,
+    mut interaction_events: MessageWriter<InteractionEvent>,
+    query: Query<(Entity, &Approach), (With<TheMan>, Without<Route>)>,
+) {
+    for (entity, approach) in &query {
+        trigger_events.write(Trigger {
+            state: State::Action,
+            direction: approach.facing,
+        });
+        interaction_events.write(InteractionEvent {
+            id: approach.id.clone(),
+            verb: approach.verb,
+        });
+        commands.entity(entity).remove::<Approach>();
+    }
+}
+
+// Every so often complain about the cold while standing out in the snow.
+fn handle_cold(
     time: Res<Time>,
-    audio_assets: Res<AudioAssets>,
-    mut query: Query<(&State, &mut StepTimer, &mut FootStep), With<TheMan>>,
+    mut bubble_events: MessageWriter<BubbleRequest>,
+    mut query: Query<(Entity, &mut ColdTimer, &Transform), With<TheMan>>,
+    regions: Query<(&Transform, &SurfaceRegion)>,
 ) {
-    for (state, mut timer, mut footstep) in &mut query {
+    for (entity, mut timer, transform) in &mut query {
+        let position = transform.translation.truncate();
+        if surface::surface_at(position, regions.iter()) != surface::Surface::Snow {
+            timer.0.reset();
+            continue;
+        }
+
+        timer.0.tick(time.delta());
+        if timer.0.just_finished() {
+            bubble_events.write(BubbleRequest {
+                entity,
+                content: BubbleContent::Text("Brr, it's cold out here!".to_string()),
+                style: BubbleStyle::Thought,
+                duration: COLD_DURATION,
+            });
+        }
+    }
+}
+
+// Move the man based on the current state, speeding up and slowing down with the grip of the surface.
+fn handle_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut trigger_events: MessageWriter<Trigger>,
+    mut sprite_position: Query<
+        (
+            Entity,
+            &State,
+            &Direction,
+            &mut Transform,
+            &mut Velocity,
+            Option<&mut Route>,
+            Has<Run>,
+        ),
+        With<TheMan>,
+    >,
+    regions: Query<(&Transform, &SurfaceRegion), Without<TheMan>>,
+) {
+    for (entity, state, direction, mut transform, mut velocity, mut route, running) in &mut sprite_position {
+        let position = transform.translation.truncate();
+        let speed = if running { RUNNING_SPEED } else { WALKING_SPEED };
+
+        // Follow the route waypoints when there is one, otherwise walk in the current direction.
+        let desired = if let Some(route) = &route {
+            let Some(waypoint) = route.0.front().copied() else {
+                commands.entity(entity).remove::<Route>();
+                velocity.0 = Vec2::ZERO;
+                trigger_events.write(Trigger {
+                    state: State::Idle,
+                    direction: *direction,
+                });
+                continue;
+            };
+            let offset = waypoint - position;
+
+            // Face the way the man is walking, keeping the current facing on vertical moves.
+            let heading = if offset.x < -f32::EPSILON {
+                Direction::Left
+            } else if offset.x > f32::EPSILON {
+                Direction::Right
+            } else {
+                *direction
+            };
+            if *state != State::Walking || heading != *direction {
+                trigger_events.write(Trigger {
+                    state: State::Walking,
+                    direction: heading,
+                });
+            }
+
+            // Ease into the final waypoint.
+            let limit = if route.0.len() == 1 {
+                speed.min(offset.length() * ARRIVE_GAIN)
+            } else {
+                speed
+            };
+            offset.normalize_or_zero() * limit
+        } else {
+            match *state {
+                State::Idle | State::Action => Vec2::ZERO,
+                State::Walking => match *direction {
+                    Direction::Left => Vec2::NEG_X * speed,
+                    Direction::Right => Vec2::X * speed,
+                },
+            }
+        };
+
+        let friction = surface::surface_at(position, regions.iter()).friction();
+        let rate = if desired.length() > velocity.0.length() {
+            ACCELERATION
+        } else {
+            DECELERATION
+        };
+        velocity.0 = velocity.0.move_towards(desired, rate * friction * time.delta_secs());
+        let step = velocity.0 * time.delta_secs();
+
+        // Snap onto waypoints rather than circling them.
+        if let Some(route) = &mut route
+            && let Some(waypoint) = route.0.front().copied()
+            && position.distance(waypoint) <= step.length().max(ARRIVE_DISTANCE)
+        {
+            transform.translation = waypoint.extend(transform.translation.z);
+            route.0.pop_front();
+            if route.0.is_empty() {
+                velocity.0 = Vec2::ZERO;
+            }
+        } else {
+            transform.translation += step.extend(0.0);
+        }
+    }
+}
+
+fn handle_audio(
+    time: Res<Time>,
+    mut sound_events: MessageWriter<PlaySound>,
+    mut query: Query<(&State, &Transform, &mut StepTimer, &mut FootStep, Has<Run>), With<TheMan>>,
+    regions: Query<(&Transform, &SurfaceRegion)>,
+) {
+    for (state, transform, mut timer, mut footstep, running) in &mut query {
+        // Running takes quicker steps.
+        let cadence = if running { RUNNING_TIMER } else { WALKING_TIMER };
+
         match *state {
             State::Walking => {
                 timer.0.tick(time.delta());
                 if timer.0.just_finished() {
-                    match *footstep {
-                        FootStep::Left => {
-                            // let audio = [audio_assets.left_step_indoor_1, audio_assets.left_step_indoor_2, audio_assets.left_step_indoor_3].choose(rng())
-                            commands.spawn((
-                                AudioPlayer::new(
-                                    audio_assets.left_steps[rng().random_range(0..audio_assets.left_steps.len())]
-                                        .clone(),
-                                ),
-                                PlaybackSettings::DESPAWN.with_volume(Volume::Linear(WALKING_VOLUME)),
-                            ));
-                            timer.0.set_duration(Duration::from_secs_f32(WALKING_TIMER));
-                            *footstep = FootStep::Right;
-                        }
-                        FootStep::Right => {
-                            commands.spawn((
-                                AudioPlayer::new(
-                                    audio_assets.right_steps[rng().random_range(0..audio_assets.right_steps.len())]
-                                        .clone(),
-                                ),
-                                PlaybackSettings::DESPAWN.with_volume(Volume::Linear(WALKING_VOLUME)),
-                            ));
-                            timer.0.set_duration(Duration::from_secs_f32(WALKING_TIMER));
-                            *footstep = FootStep::Left;
-                        }
-                    }
+                    // Step from the bank for whatever the man is standing on.
+                    let surface = surface::surface_at(transform.translation.truncate(), regions.iter());
+                    let side = match *footstep {
+                        FootStep::Left => "left",
+                        FootStep::Right => "right",
+                    };
+                    sound_events.write(PlaySound {
+                        bank: format!("theman/{side}_footstep_{}", surface.name()),
+                        emitter: None,
+                    });
+                    timer.0.set_duration(Duration::from_secs_f32(cadence));
+                    *footstep = match *footstep {
+                        FootStep::Left => FootStep::Right,
+                        FootStep::Right => FootStep::Left,
+                    };
                 }
             }
             _ => {
@@ -218,16 +570,116 @@ fn handle_audio(
     }
 }
 
-// Change the man's direction using the idle timer.
-fn idle_action(time: Res<Time>, mut query: Query<(&mut IdleTimer, &mut Sprite, &State), With<TheMan>>) {
-    for (mut timer, mut sprite, state) in &mut query {
-        if *state == State::Idle {
-            timer.0.tick(time.delta());
-            if timer.0.just_finished() {
-                sprite.flip_x = !sprite.flip_x;
-            }
-        } else {
+// Pick a weighted idle behaviour when the idle timer finishes, favouring nearby props.
+fn idle_action(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut trigger_events: MessageWriter<Trigger>,
+    mut query: Query<(Entity, &mut IdleTimer, &Transform, &State), (With<TheMan>, Without<IdleAnimation>)>,
+    trees: Query<(&Transform, &tree::State), With<Tree>>,
+    fireplaces: Query<(&Transform, &fireplace::State), With<Fireplace>>,
+) {
+    let mut rng = rng();
+
+    for (entity, mut timer, transform, state) in &mut query {
+        if *state != State::Idle {
             timer.0.reset();
+            continue;
+        }
+
+        timer.0.tick(time.delta());
+        if !timer.0.just_finished() {
+            continue;
+        }
+        timer.0 = Timer::from_seconds(rng.random_range(IDLE_DELAY_MIN..=IDLE_DELAY_MAX), TimerMode::Once);
+
+        let x = transform.translation.x;
+        let nearby = |other: &Transform| (other.translation.x - x).abs() <= IDLE_NEARBY_DISTANCE;
+        let lit_tree = trees
+            .iter()
+            .find(|(tree_transform, tree_state)| **tree_state == tree::State::On && nearby(tree_transform))
+            .map(|(tree_transform, _)| tree_transform.translation.x);
+        let warm = fireplaces.iter().any(|(fireplace_transform, fireplace_state)| {
+            *fireplace_state == fireplace::State::Running && nearby(fireplace_transform)
+        });
+
+        // A cosy fire makes the man sleepy, a lit tree catches his eye.
+        let weights = [
+            (IdleBehaviour::LookAround, 4),
+            (IdleBehaviour::Stretch, 2),
+            (IdleBehaviour::Yawn, if warm { 4 } else { 1 }),
+            (IdleBehaviour::GlanceAtTree, if lit_tree.is_some() { 5 } else { 0 }),
+        ];
+        let total: u32 = weights.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng.random_range(0..total);
+        let Some(behaviour) = weights.iter().find_map(|(behaviour, weight)| {
+            if roll < *weight {
+                Some(*behaviour)
+            } else {
+                roll -= weight;
+                None
+            }
+        }) else {
+            continue;
+        };
+
+        // Animated behaviours last as long as their frames, turning goes through the trigger so the
+        // direction always matches the sprite.
+        let duration = match behaviour.frames() {
+            Some((first, last)) => (last - first + 1) as f32 / f32::from(IDLE_FPS),
+            None => {
+                if let Some(tree_x) = lit_tree {
+                    trigger_events.write(Trigger {
+                        state: State::Idle,
+                        direction: if tree_x < x { Direction::Left } else { Direction::Right },
+                    });
+                }
+                GLANCE_DURATION
+            }
+        };
+
+        commands.entity(entity).insert(IdleAnimation {
+            behaviour,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        });
+    }
+}
+
+// Play the frames for the current idle behaviour, going back to standing when it ends or he stops idling.
+fn handle_idle_animation(
+    mut commands: Commands,
+    time: Res<Time>,
+    sprite_assets: Res<SpriteAssets>,
+    characters: Res<CharacterSprites>,
+    mut query: Query<(Entity, &mut IdleAnimation, &mut AnimationConfig, &mut Sprite, &State), With<TheMan>>,
+) {
+    for (entity, mut animation, mut config, mut sprite, state) in &mut query {
+        animation.timer.tick(time.delta());
+
+        let Some((first, last)) = animation.behaviour.frames() else {
+            if *state != State::Idle || animation.timer.is_finished() {
+                commands.entity(entity).remove::<IdleAnimation>();
+            }
+            continue;
+        };
+
+        if animation.is_added() {
+            sprite.image = sprite_assets.idle_sprite.clone();
+            sprite.texture_atlas = Some(TextureAtlas {
+                layout: sprite_assets.idle_layout.clone(),
+                index: first,
+            });
+            *config = AnimationConfig::new(first, last, IDLE_FPS);
+        }
+
+        if *state != State::Idle || animation.timer.is_finished() {
+            // Walking has already changed the sprite, otherwise stand back up.
+            if *state == State::Idle {
+                let facing_left = sprite.flip_x;
+                characters.stand(&mut sprite, facing_left);
+            }
+            *config = AnimationConfig::new(0, WALKING_LAST_FRAME, WALKING_FPS);
+            commands.entity(entity).remove::<IdleAnimation>();
         }
     }
 }
@@ -236,58 +688,29 @@ fn idle_action(time: Res<Time>, mut query: Query<(&mut IdleTimer, &mut Sprite, &
 fn init(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    characters: Res<CharacterSprites>,
     mut texture_layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
-    // Load the sprite sheets.
+    // Load the idle sprite sheet, walking and standing are shared with the other characters.
     let sprites = SpriteAssets {
-        walking_sprite: asset_server.load("theman/theman_walking_animation.png"),
-        walking_layout: texture_layouts.add(TextureAtlasLayout::from_grid(UVec2::splat(32), 9, 1, None, None)),
-        standing_sprite: asset_server.load("theman/theman_standing.png"),
-        standing_layout: texture_layouts.add(TextureAtlasLayout::from_grid(UVec2::splat(32), 1, 1, None, None)),
-    };
-    commands.insert_resource(sprites.clone());
-
-    // Load the sound effects.
-    let mut audio = AudioAssets {
-        left_steps: vec![],
-        right_steps: vec![],
+        idle_sprite: asset_server.load("theman/theman_idle_animation.png"),
+        idle_layout: texture_layouts.add(TextureAtlasLayout::from_grid(UVec2::splat(32), 18, 1, None, None)),
     };
-    audio
-        .left_steps
-        .push(asset_server.load("theman/left_footstep_indoor_1.ogg"));
-    audio
-        .left_steps
-        .push(asset_server.load("theman/left_footstep_indoor_2.ogg"));
-    audio
-        .left_steps
-        .push(asset_server.load("theman/left_footstep_indoor_3.ogg"));
-    audio
-        .right_steps
-        .push(asset_server.load("theman/right_footstep_indoor_1.ogg"));
-    audio
-        .right_steps
-        .push(asset_server.load("theman/right_footstep_indoor_2.ogg"));
-    audio
-        .right_steps
-        .push(asset_server.load("theman/right_footstep_indoor_3.ogg"));
-    commands.insert_resource(audio);
+    commands.insert_resource(sprites);
 
     // Create the man starting in the idle state.
+    let mut sprite = Sprite::default();
+    characters.stand(&mut sprite, false);
     commands.spawn((
-        Sprite {
-            image: sprites.standing_sprite,
-            texture_atlas: Some(TextureAtlas {
-                layout: sprites.standing_layout,
-                index: 0,
-            }),
-            ..default()
-        },
+        sprite,
         Transform::from_scale(Vec3::splat(SPRITE_SCALE)).with_translation(Vec3::new(-64.0, -74.0, 10.0)),
         TheMan,
-        AnimationConfig::new(0, 8, 10),
+        AnimationConfig::new(0, WALKING_LAST_FRAME, WALKING_FPS),
         State::Idle,
-        IdleTimer(Timer::from_seconds(5.0, TimerMode::Repeating)),
+        Velocity::default(),
+        IdleTimer(Timer::from_seconds(IDLE_DELAY_MIN, TimerMode::Once)),
         StepTimer(Timer::from_seconds(0.0, TimerMode::Repeating)),
+        ColdTimer(Timer::from_seconds(COLD_INTERVAL, TimerMode::Repeating)),
         Direction::Right,
         FootStep::Left,
         SpatialListener::new(AUDIO_WIDTH),
@@ -301,49 +724,27 @@ fn init(
 // Read animation messages and update animation state.
 fn trigger_animation(
     mut events: MessageReader<Trigger>,
-    sprite_assets: Res<SpriteAssets>,
+    characters: Res<CharacterSprites>,
     query: Single<(&mut AnimationConfig, &mut Sprite, &mut State, &mut Direction), With<TheMan>>,
 ) {
     let (mut config, mut sprite, mut state, mut direction) = query.into_inner();
     for event in events.read() {
         // Only update if state changed
         if *state != event.state || *direction != event.direction {
+            let facing_left = event.direction == Direction::Left;
             match event.state {
-                State::Idle => {
-                    sprite.image = sprite_assets.standing_sprite.clone();
-                    sprite.texture_atlas = Some(TextureAtlas {
-                        layout: sprite_assets.standing_layout.clone(),
-                        index: 0,
-                    });
-                    sprite.flip_x = *direction == Direction::Left;
-                }
+                State::Idle => characters.stand(&mut sprite, facing_left),
 
+                // An action is a single frame, done as soon as it's shown.
                 State::Action => {
-                    sprite.image = sprite_assets.standing_sprite.clone();
+                    characters.stand(&mut sprite, facing_left);
                     sprite.texture_atlas = None;
                 }
 
-                State::Walking => match event.direction {
-                    Direction::Left => {
-                        sprite.image = sprite_assets.walking_sprite.clone();
-                        sprite.texture_atlas = Some(TextureAtlas {
-                            layout: sprite_assets.walking_layout.clone(),
-                            index: 0,
-                        });
-                        sprite.flip_x = true;
-                        config.frame_timer = AnimationConfig::timer_from_fps(config.fps);
-                    }
-
-                    Direction::Right => {
-                        sprite.image = sprite_assets.walking_sprite.clone();
-                        sprite.texture_atlas = Some(TextureAtlas {
-                            layout: sprite_assets.walking_layout.clone(),
-                            index: 0,
-                        });
-                        sprite.flip_x = false;
-                        config.frame_timer = AnimationConfig::timer_from_fps(config.fps);
-                    }
-                },
+                State::Walking => {
+                    characters.walk(&mut sprite, facing_left);
+                    config.frame_timer = AnimationConfig::timer_from_fps(config.fps);
+                }
             }
 
             *state = event.state;
@@ -2,12 +2,14 @@ use bevy::prelude::*;
 use rand::Rng;
 
 use crate::{
+    analysis::Beat,
     animation::AnimationConfig,
-    interaction::{Highlight, Interactable, InteractionEvent},
+    interaction::{Facing, Highlight, Interactable, InteractionEvent, Verb},
+    navigation::Obstacle,
 };
 
 #[derive(Clone, Component, Copy, PartialEq)]
-enum State {
+pub enum State {
     Off,
     On,
 }
@@ -20,20 +22,27 @@ struct SpriteAssets {
 }
 
 #[derive(Component)]
-struct Tree;
+pub struct Tree;
 
 const SPRITE_SCALE: f32 = 2.0;
 const SPRITE_WIDTH: f32 = 14.;
 const SPRITE_HEIGHT: f32 = 16.;
 
+const OBSTACLE_DEPTH: f32 = 6.0;
+const STAND_OFFSET: f32 = 24.0;
+
 const INTERACTABLE_ID: &str = "tree";
 
+// How long the lights hold a pattern after a beat before sparkling on their own again.
+const BEAT_HOLD: f32 = 1.5;
+
 // Add the animation systems.
 pub fn add_systems(app: &mut App) {
     app.add_systems(Startup, init).add_systems(
         Update,
         (
             handle_animations,
+            handle_beats,
             handle_highlight,
             handle_highlight_reset,
             handle_interaction,
@@ -58,17 +67,41 @@ fn handle_animations(time: Res<Time>, mut query: Query<(&mut AnimationConfig, &m
         if config.frame_timer.just_finished()
             && let Some(atlas) = &mut sprite.texture_atlas
         {
-            // Tree sparkles are random.
-            let mut new_index = rng.random_range(config.first_index..=config.last_index);
-            while new_index == atlas.index {
-                new_index = rng.random_range(config.first_index..=config.last_index);
-            }
-            atlas.index = new_index;
+            sparkle(&config, atlas, &mut rng);
             config.frame_timer = AnimationConfig::timer_from_fps(config.fps);
         }
     }
 }
 
+// Change the lights' pattern on each beat of the music, holding it until the next.
+fn handle_beats(
+    mut events: MessageReader<Beat>,
+    mut query: Query<(&mut AnimationConfig, &mut Sprite, &State), With<Tree>>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+    let mut rng = rand::rng();
+
+    for (mut config, mut sprite, state) in &mut query {
+        if *state == State::On
+            && let Some(atlas) = &mut sprite.texture_atlas
+        {
+            sparkle(&config, atlas, &mut rng);
+            config.frame_timer = Timer::from_seconds(BEAT_HOLD, TimerMode::Once);
+        }
+    }
+}
+
+// Tree sparkles are random, always moving to a different frame.
+fn sparkle(config: &AnimationConfig, atlas: &mut TextureAtlas, rng: &mut impl Rng) {
+    let mut new_index = rng.random_range(config.first_index..=config.last_index);
+    while new_index == atlas.index {
+        new_index = rng.random_range(config.first_index..=config.last_index);
+    }
+    atlas.index = new_index;
+}
+
 // Apply a pulsing scale effect to highlighted tree.
 fn handle_highlight(
     time: Res<Time>,
@@ -107,6 +140,7 @@ fn handle_interaction(
 ) {
     for event in events.read() {
         if event.id == INTERACTABLE_ID
+            && event.verb == Verb::Use
             && let Ok((mut state, mut sprite)) = query.single_mut()
         {
             match *state {
@@ -169,6 +203,12 @@ fn init(
             height: SPRITE_HEIGHT * SPRITE_SCALE,
             width: SPRITE_WIDTH * SPRITE_SCALE,
             first: true,
+            stand_offset: STAND_OFFSET,
+            facing: Facing::Left,
+        },
+        Obstacle {
+            width: SPRITE_WIDTH * SPRITE_SCALE,
+            depth: OBSTACLE_DEPTH,
         },
     ));
 }